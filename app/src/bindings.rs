@@ -0,0 +1,269 @@
+use std::fmt;
+
+/// A game control that keys can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Left,
+    Right,
+    Thrust,
+    Fire,
+    Shield,
+    Start,
+    Pause,
+    Slow,
+    Rewind,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Left,
+        Action::Right,
+        Action::Thrust,
+        Action::Fire,
+        Action::Shield,
+        Action::Start,
+        Action::Pause,
+        Action::Slow,
+        Action::Rewind,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Thrust => "thrust",
+            Action::Fire => "fire",
+            Action::Shield => "shield",
+            Action::Start => "start",
+            Action::Pause => "pause",
+            Action::Slow => "slow",
+            Action::Rewind => "rewind",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .cloned()
+            .find(|action| action.name() == name)
+    }
+
+    /// The bit of this action in a `Controls` input bitmask.
+    pub fn bit(&self) -> u32 {
+        match self {
+            Action::Left => 1,
+            Action::Right => 2,
+            Action::Thrust => 4,
+            Action::Fire => 8,
+            Action::Shield => 16,
+            Action::Start => 32,
+            Action::Pause => 64,
+            Action::Slow => 128,
+            Action::Rewind => 256,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid binding on line {}", self.line)
+    }
+}
+
+/// A profile of key bindings, where each key triggers one action, and an
+/// action may have any number of keys. Keys are named as by the `key` property
+/// of browser keyboard events, compared case-insensitively, with the space
+/// bar named "space".
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    keys: Vec<(String, Action)>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Bindings { keys: Vec::new() }
+    }
+
+    pub fn standard() -> Self {
+        let mut bindings = Bindings::new();
+        for (action, keys) in [
+            (Action::Left, &["arrowleft", "j"][..]),
+            (Action::Right, &["arrowright", "l"][..]),
+            (Action::Thrust, &["arrowup", "i"][..]),
+            (Action::Fire, &["f"][..]),
+            (Action::Shield, &["s"][..]),
+            (Action::Start, &["enter"][..]),
+            (Action::Pause, &["p", "escape"][..]),
+            (Action::Slow, &["d"][..]),
+            (Action::Rewind, &["r"][..]),
+        ]
+        .iter()
+        {
+            for key in keys.iter() {
+                bindings.bind(*action, key);
+            }
+        }
+        bindings
+    }
+
+    /// Bind `key` to `action`, replacing any action it was bound to.
+    pub fn bind(&mut self, action: Action, key: &str) {
+        let key = normalize(key);
+        self.keys.retain(|(bound, _)| *bound != key);
+        self.keys.push((key, action));
+    }
+
+    pub fn unbind(&mut self, key: &str) {
+        let key = normalize(key);
+        self.keys.retain(|(bound, _)| *bound != key);
+    }
+
+    /// Remove all keys bound to `action`.
+    pub fn clear(&mut self, action: Action) {
+        self.keys.retain(|(_, bound)| *bound != action);
+    }
+
+    pub fn action(&self, key: &str) -> Option<Action> {
+        let key = normalize(key);
+        (self.keys.iter()).find_map(|(bound, action)| Some(*action).filter(|_| *bound == key))
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<&str> {
+        (self.keys.iter())
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// Serialize as lines of an action name followed by its keys, separated
+    /// by spaces, e.g. "left arrowleft j".
+    pub fn serialize(&self) -> String {
+        let mut text = String::new();
+        for action in Action::ALL.iter() {
+            text.push_str(action.name());
+            for key in self.keys(*action) {
+                text.push(' ');
+                text.push_str(key);
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn deserialize(text: &str) -> Result<Self, ParseError> {
+        let mut bindings = Bindings::new();
+        for (i, line) in text.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            if let Some(name) = words.next() {
+                let action = Action::from_name(name).ok_or(ParseError { line: i + 1 })?;
+                for key in words {
+                    bindings.bind(action, key);
+                }
+            }
+        }
+        Ok(bindings)
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings::standard()
+    }
+}
+
+fn normalize(key: &str) -> String {
+    match key {
+        " " => "space".to_string(),
+        _ => key.to_lowercase(),
+    }
+}
+
+/// The keys currently held, and the input bitmask they produce.
+pub struct Keyboard {
+    pub bindings: Bindings,
+    held: Vec<String>,
+}
+
+impl Keyboard {
+    pub fn new(bindings: Bindings) -> Self {
+        Keyboard {
+            bindings,
+            held: Vec::new(),
+        }
+    }
+
+    /// Record a key press or release, returning whether the key is bound.
+    pub fn key(&mut self, key: &str, down: bool) -> bool {
+        let key = normalize(key);
+        self.held.retain(|held| *held != key);
+        if down {
+            self.held.push(key.clone());
+        }
+        self.bindings.action(&key).is_some()
+    }
+
+    /// Release all keys, e.g. when the page loses focus.
+    pub fn release(&mut self) {
+        self.held.clear();
+    }
+
+    pub fn input(&self) -> u32 {
+        (self.held.iter())
+            .filter_map(|key| self.bindings.action(key))
+            .fold(0, |input, action| input | action.bit())
+    }
+}
+
+#[cfg(test)]
+mod test_bindings {
+    use super::*;
+
+    #[test]
+    fn test_multiple_keys() {
+        let mut keyboard = Keyboard::new(Bindings::standard());
+        assert!(keyboard.key("ArrowLeft", true));
+        assert!(keyboard.key("J", true));
+        assert!(keyboard.key("ArrowLeft", false));
+        assert_eq!(keyboard.input(), Action::Left.bit());
+        keyboard.key("j", false);
+        assert_eq!(keyboard.input(), 0);
+        assert!(!keyboard.key("q", true));
+        assert_eq!(keyboard.input(), 0);
+    }
+
+    #[test]
+    fn test_rebind() {
+        let mut keyboard = Keyboard::new(Bindings::standard());
+        keyboard.key(" ", true);
+        assert_eq!(keyboard.input(), 0);
+        keyboard.bindings.bind(Action::Fire, " ");
+        keyboard.bindings.bind(Action::Shield, "f");
+        assert_eq!(keyboard.input(), Action::Fire.bit());
+        assert_eq!(keyboard.bindings.keys(Action::Fire), vec!["space"]);
+        assert_eq!(keyboard.bindings.keys(Action::Shield), vec!["s", "f"]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut bindings = Bindings::standard();
+        bindings.clear(Action::Pause);
+        bindings.bind(Action::Thrust, "w");
+        let text = bindings.serialize();
+        assert!(text.contains("thrust arrowup i w\n"));
+        assert!(text.contains("pause\n"));
+        assert_eq!(Bindings::deserialize(&text).unwrap().serialize(), text);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            Bindings::deserialize("left a\n\njump space").unwrap_err(),
+            ParseError { line: 3 }
+        );
+    }
+}
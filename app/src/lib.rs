@@ -1,36 +1,219 @@
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use asteroids::Controls;
+use asteroids::audio::Synth;
+use asteroids::geometry::Point;
+use asteroids::{Controls, Game, Rules, Versus};
 
+pub mod bindings;
+use bindings::{Action, Bindings, Keyboard};
 pub mod render;
 use render::PathList;
+pub mod spectate;
+use spectate::Encoder;
+pub mod touch;
+use touch::Touch;
 
 #[wasm_bindgen]
-pub struct App(asteroids::Game);
+pub struct App {
+    game: Game,
+    synth: Option<Synth>,
+    samples: Vec<f32>,
+    encoder: Encoder,
+    keyboard: Keyboard,
+    touch: Touch,
+}
 
 #[wasm_bindgen]
 impl App {
     pub fn new() -> Self {
-        App(asteroids::Game::new())
+        App::with_game(Game::new())
+    }
+
+    /// Create an app for `count` local players sharing each level.
+    pub fn with_players(count: usize, friendly_fire: bool) -> Self {
+        App::with_game(Game::with_rules(Rules::coop(count, friendly_fire)))
+    }
+
+    /// Create an app for `count` players competing in a versus round.
+    pub fn versus(count: usize, bounded: bool) -> Self {
+        let versus = Versus {
+            bounded,
+            ..Versus::standard()
+        };
+        App::with_game(Game::with_rules(Rules::versus(count, versus)))
     }
 
     pub fn step(&mut self, dt: f64, input: u32) -> () {
-        if dt <= 0.0 {
-            return ();
+        self.step_players(dt, &[input]);
+    }
+
+    /// Step with one input bitmask per player. Held keys and touches are
+    /// merged into the first player's controls.
+    pub fn step_players(&mut self, dt: f64, inputs: &[u32]) {
+        let keys = self.keyboard.input();
+        let (first, rest) = inputs.split_first().unwrap_or((&0, &[]));
+        let first = if self.touch.is_active() {
+            self.touch.controls(first | keys)
+        } else {
+            Controls::new(first | keys)
+        };
+        let controls = (Some(first).into_iter())
+            .chain(rest.iter().map(|&input| Controls::new(input)))
+            .collect::<Vec<_>>();
+        self.step_controls(dt, &controls);
+    }
+
+    /// Step with analog input, such as from a gamepad, where `turn` is within
+    /// range [-1, 1], `thrust` within [0, 1], and `aim` is an optional
+    /// direction to face, in radians. `buttons` is an input bitmask, of which
+    /// left, right and thrust are ignored.
+    pub fn step_gamepad(
+        &mut self,
+        dt: f64,
+        turn: f64,
+        thrust: f64,
+        aim: Option<f64>,
+        buttons: u32,
+    ) {
+        let keys = Controls::new(self.keyboard.input());
+        let turn = if turn == 0.0 { keys.turn() } else { turn };
+        let thrust = thrust.max(keys.thrust_amount());
+        let buttons = buttons | self.keyboard.input();
+        self.step_controls(dt, &[Controls::analog(turn, thrust, aim, buttons)]);
+    }
+
+    /// Set the rate at which game time passes, relative to real time.
+    pub fn set_time_scale(&mut self, scale: f64) {
+        self.game.set_time_scale(scale);
+    }
+
+    /// Record a key press or release, by its `KeyboardEvent.key` name.
+    /// Returns whether the key is bound, so its default action can be
+    /// prevented.
+    pub fn key(&mut self, key: &str, down: bool) -> bool {
+        self.keyboard.key(key, down)
+    }
+
+    /// Record a touch starting or moving, at a position in game coordinates.
+    pub fn touch(&mut self, id: u32, x: f64, y: f64) {
+        self.touch.touch(id, Point::new(x, y));
+    }
+
+    pub fn touch_end(&mut self, id: u32) {
+        self.touch.end(id);
+    }
+
+    /// Bind `key` to the action named `action`, returning false if there is
+    /// no such action.
+    pub fn bind(&mut self, action: &str, key: &str) -> bool {
+        match Action::from_name(action) {
+            Some(action) => {
+                self.keyboard.bindings.bind(action, key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn unbind(&mut self, key: &str) {
+        self.keyboard.bindings.unbind(key);
+    }
+
+    /// The current key bindings, serialized for saving.
+    pub fn bindings(&self) -> String {
+        self.keyboard.bindings.serialize()
+    }
+
+    /// Load serialized key bindings, returning false if they are invalid.
+    pub fn load_bindings(&mut self, text: &str) -> bool {
+        match Bindings::deserialize(text) {
+            Ok(bindings) => {
+                self.keyboard.bindings = bindings;
+                true
+            }
+            Err(_) => false,
         }
-        self.0.step(dt, Controls::new(input))
+    }
+
+    pub fn reset_bindings(&mut self) {
+        self.keyboard.bindings = Bindings::standard();
+    }
+
+    pub fn pause(&mut self) {
+        self.keyboard.release();
+        self.touch.release();
+        self.game.pause()
+    }
+
+    /// Start producing sound, at the sample rate of the audio output.
+    pub fn enable_audio(&mut self, sample_rate: u32) {
+        self.synth = Some(Synth::new(sample_rate));
+    }
+
+    /// Render the next `length` mono samples, returning a pointer to them.
+    pub fn render_audio(&mut self, length: usize) -> *const f32 {
+        self.samples.resize(length, 0.0);
+        if let Some(synth) = &mut self.synth {
+            synth.render(&mut self.samples);
+        }
+        self.samples.as_ptr()
+    }
+
+    /// Encode the current frame for spectators. See `spectate::Decoder`.
+    pub fn broadcast(&mut self) -> Vec<u8> {
+        self.encoder.encode(&self.game)
+    }
+
+    /// Make the next broadcast frame one that new spectators can start from.
+    pub fn broadcast_keyframe(&mut self) {
+        self.encoder.keyframe();
     }
 
     pub fn render(&self) -> PathList {
         let mut list = PathList::new();
-        if let Some(player) = self.0.player() {
+        for player in self.game.players() {
             render::player(player, &mut list);
         }
-        render::asteroids(self.0.asteroids(), &mut list);
-        render::blasts(self.0.blasts(), &mut list);
-        render::particles(self.0.particles(), &mut list);
-        render::polylines(self.0.text(), 1.0, &mut list);
-        render::polylines(&self.0.hud(), 0.3, &mut list);
+        render::asteroids(self.game.asteroids(), &mut list);
+        render::blasts(self.game.blasts(), &mut list);
+        render::particles(self.game.particles(), &mut list);
+        render::polylines(self.game.text(), 1.0, &mut list);
+        for (alpha, text) in self.game.popups() {
+            render::polylines(&text, alpha, &mut list);
+        }
+        render::polylines(&self.game.hud(), 0.3, &mut list);
+        render::polylines(&self.game.bullet_time_frame(), 0.6, &mut list);
+        self.touch.render(&mut list);
         list
     }
 }
+
+impl App {
+    fn with_game(game: Game) -> Self {
+        App {
+            touch: Touch::new(game.bounds()),
+            game,
+            synth: None,
+            samples: Vec::new(),
+            encoder: Encoder::new(),
+            keyboard: Keyboard::new(Bindings::standard()),
+        }
+    }
+
+    fn step_controls(&mut self, dt: f64, controls: &[Controls]) {
+        if dt <= 0.0 {
+            return;
+        }
+        self.game.step_players(dt, controls);
+
+        let events = self.game.drain_events();
+        if let Some(synth) = &mut self.synth {
+            for event in events.iter() {
+                synth.handle(event);
+            }
+            let players = self.game.players();
+            synth.set_thrust(players.iter().any(|player| player.is_thrusting()));
+            synth.set_shield(players.iter().any(|player| player.is_shielding()));
+        }
+    }
+}
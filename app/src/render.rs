@@ -16,12 +16,37 @@ pub enum PathEnd {
     Closed = 1,
 }
 
+/// A point at the single precision canvas drawing needs, so that points
+/// can be read from JS as a `Float32Array`. The simulation itself keeps
+/// double precision.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vertex {
+    x: f32,
+    y: f32,
+}
+
+impl Vertex {
+    pub fn to_point(self) -> Point {
+        Point::new(self.x as f64, self.y as f64)
+    }
+}
+
+impl From<&Point> for Vertex {
+    fn from(point: &Point) -> Self {
+        Vertex {
+            x: point.x as f32,
+            y: point.y as f32,
+        }
+    }
+}
+
 #[wasm_bindgen]
 pub struct PathList {
     paths: Vec<Path>,
-    alphas: Vec<f64>,
+    alphas: Vec<f32>,
     ends: Vec<PathEnd>,
-    points: Vec<Point>,
+    points: Vec<Vertex>,
 }
 
 impl PathList {
@@ -42,11 +67,25 @@ impl PathList {
             offset: self.points.len(),
             length: points.len(),
         });
-        self.alphas.push(alpha);
+        self.alphas.push(alpha as f32);
         self.ends.push(end);
-        self.points.append(points);
+        self.points
+            .extend(points.drain(..).map(|point| Vertex::from(&point)));
         self
     }
+
+    /// Each path, as its points, alpha and whether it is closed.
+    pub fn iter(&self) -> impl Iterator<Item = (&[Vertex], f64, bool)> {
+        (self
+            .paths
+            .iter()
+            .zip(self.alphas.iter())
+            .zip(self.ends.iter()))
+        .map(move |((path, alpha), end)| {
+            let points = &self.points[path.offset..path.offset + path.length];
+            (points, *alpha as f64, matches!(end, PathEnd::Closed))
+        })
+    }
 }
 
 #[wasm_bindgen]
@@ -59,7 +98,7 @@ impl PathList {
         self.paths.as_ptr()
     }
 
-    pub fn alphas(&self) -> *const f64 {
+    pub fn alphas(&self) -> *const f32 {
         self.alphas.as_ptr()
     }
 
@@ -71,7 +110,7 @@ impl PathList {
         self.points.len()
     }
 
-    pub fn points(&self) -> *const Point {
+    pub fn points(&self) -> *const Vertex {
         self.points.as_ptr()
     }
 }
@@ -105,11 +144,15 @@ pub fn blasts(blasts: &[Blast], list: &mut PathList) {
 pub fn particles(particles: &[Particle], list: &mut PathList) {
     for particle in particles.iter() {
         let (a, b) = particle.endpoints();
-        let alpha = 0.5 + (0.5 - (particle.rotation() / PI).rem_euclid(1.0)).abs();
-        list.push(&mut vec![a, b], alpha, PathEnd::Open);
+        list.push(&mut vec![a, b], particle_alpha(particle), PathEnd::Open);
     }
 }
 
+/// Particles flicker as they spin.
+pub fn particle_alpha(particle: &Particle) -> f64 {
+    0.5 + (0.5 - (particle.rotation() / PI).rem_euclid(1.0)).abs()
+}
+
 pub fn polylines(polylines: &[Vec<Point>], alpha: f64, list: &mut PathList) {
     for polyline in polylines {
         list.push(&mut polyline.clone(), alpha, PathEnd::Open);
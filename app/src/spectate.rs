@@ -0,0 +1,672 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+
+use asteroids::geometry::Point;
+use asteroids::motion::Placement;
+use asteroids::Game;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::render;
+use crate::render::{PathEnd, PathList};
+
+// Frames are encoded as:
+//
+//     tick: u32
+//     keyframe: u8
+//     definitions: varint count, then each
+//         id: varint, alpha: u8, closed: u8, length: varint, points: (f32, f32)...
+//     bodies: varint count, then each
+//         shape id: varint, then x, y, rotation: zigzag varint deltas
+//     paths: varint count, then each
+//         alpha: u8, closed: u8, length: varint, then points: zigzag varint
+//         (x, y), the first absolute and the rest relative to the one before
+//
+// Rigid outlines, such as asteroids and spaceships, are bodies: their shapes
+// are defined once, in the first frame they appear, and then only their
+// placements are sent, as the change from the previous frame. A body is
+// identified by its shape and its occurrence among bodies of the same shape
+// in the frame. Shapes and placements not used in a frame are forgotten by
+// both the encoder and decoder. A keyframe depends on no earlier frames, so
+// spectators may join the stream at one.
+
+const POSITION_SCALE: f64 = 16.0; // units / px
+const ROTATION_SCALE: f64 = 4096.0; // units / radian
+
+struct Body<'a> {
+    outline: &'a Vec<Point>,
+    alpha: f64,
+    closed: bool,
+    placement: &'a Placement,
+}
+
+struct Path {
+    points: Vec<Point>,
+    alpha: f64,
+    closed: bool,
+}
+
+struct Shape {
+    points: Vec<Point>,
+    alpha: f64,
+    closed: bool,
+}
+
+type Key = (u32, u32); // shape id, occurrence
+
+/// Everything `App::render` draws, in the same order, separated into bodies
+/// and free paths.
+fn scene(game: &Game) -> (Vec<Body<'_>>, Vec<Path>) {
+    let mut bodies = Vec::new();
+    let mut paths = Vec::new();
+    for player in game.players() {
+        let (hull, interior, shield) = player.outlines();
+        let placement = player.placement();
+        bodies.push(Body::new(hull, 0.9, true, placement));
+        bodies.push(Body::new(interior, 0.7, false, placement));
+        if let Some(shield) = shield {
+            bodies.push(Body::new(shield, 0.7, true, placement));
+        }
+        for (alpha, points) in player.exhaust() {
+            paths.push(Path::new(points, alpha, false));
+        }
+    }
+    for asteroid in game.asteroids() {
+        bodies.push(Body::new(
+            asteroid.polygon(),
+            0.5,
+            true,
+            asteroid.placement(),
+        ));
+    }
+    for blast in game.blasts() {
+        let (a, b) = blast.endpoints();
+        paths.push(Path::new(vec![a, b], 1.0, false));
+    }
+    for particle in game.particles() {
+        let (a, b) = particle.endpoints();
+        paths.push(Path::new(
+            vec![a, b],
+            render::particle_alpha(particle),
+            false,
+        ));
+    }
+    for text in game.text() {
+        paths.push(Path::new(text.clone(), 1.0, false));
+    }
+    for (alpha, text) in game.popups() {
+        paths.extend(
+            text.into_iter()
+                .map(|points| Path::new(points, alpha, false)),
+        );
+    }
+    for hud in game.hud() {
+        paths.push(Path::new(hud, 0.3, false));
+    }
+    for frame in game.bullet_time_frame() {
+        paths.push(Path::new(frame, 0.6, false));
+    }
+    (bodies, paths)
+}
+
+impl<'a> Body<'a> {
+    fn new(outline: &'a Vec<Point>, alpha: f64, closed: bool, placement: &'a Placement) -> Self {
+        Body {
+            outline,
+            alpha,
+            closed,
+            placement,
+        }
+    }
+
+    fn shape_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u8(quantize_alpha(self.alpha));
+        hasher.write_u8(self.closed as u8);
+        for point in self.outline {
+            hasher.write_u64(point.x.to_bits());
+            hasher.write_u64(point.y.to_bits());
+        }
+        hasher.finish()
+    }
+}
+
+impl Path {
+    fn new(points: Vec<Point>, alpha: f64, closed: bool) -> Self {
+        Path {
+            points,
+            alpha,
+            closed,
+        }
+    }
+}
+
+/// Encodes a game as a stream of frames, each relative to the one before.
+pub struct Encoder {
+    tick: u32,
+    keyframe: bool,
+    next_id: u32,
+    shapes: HashMap<u64, u32>, // shape hash -> id
+    placements: HashMap<Key, [i64; 3]>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder {
+            tick: 0,
+            keyframe: true,
+            next_id: 0,
+            shapes: HashMap::new(),
+            placements: HashMap::new(),
+        }
+    }
+
+    /// Make the next frame a keyframe, e.g. when a spectator joins.
+    pub fn keyframe(&mut self) {
+        self.keyframe = true;
+    }
+
+    pub fn encode(&mut self, game: &Game) -> Vec<u8> {
+        let (bodies, paths) = scene(game);
+        let keyframe = self.keyframe;
+        if keyframe {
+            self.shapes.clear();
+            self.placements.clear();
+            self.keyframe = false;
+        }
+
+        let mut definitions = Vec::new();
+        let mut definition_count = 0;
+        let mut records = Vec::new();
+        let mut shapes = HashMap::new();
+        let mut placements = HashMap::new();
+        let mut occurrences = HashMap::new();
+        for body in bodies.iter() {
+            let hash = body.shape_hash();
+            let id = match shapes.get(&hash).or_else(|| self.shapes.get(&hash)) {
+                Some(id) => *id,
+                None => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    write_definition(&mut definitions, id, body);
+                    definition_count += 1;
+                    id
+                }
+            };
+            shapes.insert(hash, id);
+
+            let occurrence = occurrences.entry(id).or_insert(0);
+            let key = (id, *occurrence);
+            *occurrence += 1;
+
+            let placement = quantize_placement(body.placement);
+            let previous = self.placements.get(&key).unwrap_or(&[0; 3]);
+            write_varint(&mut records, id as u64);
+            for (value, previous) in placement.iter().zip(previous.iter()) {
+                write_zigzag(&mut records, value - previous);
+            }
+            placements.insert(key, placement);
+        }
+        self.shapes = shapes;
+        self.placements = placements;
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.tick.to_le_bytes());
+        bytes.push(keyframe as u8);
+        write_varint(&mut bytes, definition_count);
+        bytes.append(&mut definitions);
+        write_varint(&mut bytes, bodies.len() as u64);
+        bytes.append(&mut records);
+        write_varint(&mut bytes, paths.len() as u64);
+        for path in paths.iter() {
+            write_path(&mut bytes, path);
+        }
+        self.tick = self.tick.wrapping_add(1);
+        bytes
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder::new()
+    }
+}
+
+/// Rebuilds renderable frames from the stream of an `Encoder`. Frames must
+/// be decoded in the order they were encoded.
+#[wasm_bindgen]
+pub struct Decoder {
+    tick: Option<u32>,
+    shapes: HashMap<u32, Shape>,
+    placements: HashMap<Key, [i64; 3]>,
+}
+
+impl Decoder {
+    /// Decode a frame, or return `None` if it is malformed. The frame is
+    /// parsed in full before any state is kept, so a malformed frame leaves
+    /// the decoder ready for the next.
+    pub fn decode(&mut self, bytes: &[u8]) -> Option<PathList> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let tick = reader.u32()?;
+        let keyframe = reader.u8()? != 0;
+        let (known_shapes, known_placements) = if keyframe {
+            (None, None)
+        } else {
+            (Some(&self.shapes), Some(&self.placements))
+        };
+
+        let mut shapes = HashMap::new();
+        for _ in 0..reader.varint()? {
+            let id = reader.varint()? as u32;
+            let alpha = reader.u8()? as f64 / 255.0;
+            let closed = reader.u8()? != 0;
+            let length = reader.varint()? as usize;
+            let mut points = Vec::with_capacity(length.min(bytes.len()));
+            for _ in 0..length {
+                points.push(Point::new(reader.f32()? as f64, reader.f32()? as f64));
+            }
+            shapes.insert(
+                id,
+                Shape {
+                    points,
+                    alpha,
+                    closed,
+                },
+            );
+        }
+
+        let mut list = PathList::new();
+        let mut placements = HashMap::new();
+        let mut occurrences = HashMap::new();
+        for _ in 0..reader.varint()? {
+            let id = reader.varint()? as u32;
+            let occurrence = occurrences.entry(id).or_insert(0);
+            let key = (id, *occurrence);
+            *occurrence += 1;
+
+            let previous = known_placements
+                .and_then(|known| known.get(&key))
+                .unwrap_or(&[0; 3]);
+            let placement = [
+                previous[0].checked_add(reader.zigzag()?)?,
+                previous[1].checked_add(reader.zigzag()?)?,
+                previous[2].checked_add(reader.zigzag()?)?,
+            ];
+            placements.insert(key, placement);
+
+            let shape =
+                (shapes.get(&id)).or_else(|| known_shapes.and_then(|known| known.get(&id)))?;
+            let mut points = dequantize_placement(&placement).transform_points(&shape.points);
+            list.push(&mut points, shape.alpha, path_end(shape.closed));
+        }
+
+        for _ in 0..reader.varint()? {
+            let alpha = reader.u8()? as f64 / 255.0;
+            let closed = reader.u8()? != 0;
+            let length = reader.varint()? as usize;
+            let mut points = Vec::with_capacity(length.min(bytes.len()));
+            let (mut x, mut y) = (0i64, 0i64);
+            for _ in 0..length {
+                x = x.checked_add(reader.zigzag()?)?;
+                y = y.checked_add(reader.zigzag()?)?;
+                points.push(Point::new(
+                    x as f64 / POSITION_SCALE,
+                    y as f64 / POSITION_SCALE,
+                ));
+            }
+            list.push(&mut points, alpha, path_end(closed));
+        }
+
+        if keyframe {
+            self.shapes.clear();
+        }
+        self.shapes.extend(shapes);
+        self.shapes.retain(|id, _| occurrences.contains_key(id));
+        self.placements = placements;
+        self.tick = Some(tick);
+        Some(list)
+    }
+}
+
+#[wasm_bindgen]
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder {
+            tick: None,
+            shapes: HashMap::new(),
+            placements: HashMap::new(),
+        }
+    }
+
+    /// Decode a frame for rendering, or return an empty list if it is
+    /// malformed.
+    pub fn receive(&mut self, frame: &[u8]) -> PathList {
+        self.decode(frame).unwrap_or_else(PathList::new)
+    }
+
+    /// The tick of the most recently decoded frame.
+    pub fn tick(&self) -> Option<u32> {
+        self.tick
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
+/// An in-memory transport that delivers each frame, in order, to every
+/// subscriber, e.g. for tests or local previews.
+pub struct Broadcast {
+    subscribers: Vec<Sender<Vec<u8>>>,
+}
+
+impl Broadcast {
+    pub fn new() -> Self {
+        Broadcast {
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self) -> Receiver<Vec<u8>> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Send a frame, dropping subscribers that have gone away.
+    pub fn send(&mut self, frame: &[u8]) {
+        (self.subscribers).retain(|subscriber| subscriber.send(frame.to_vec()).is_ok());
+    }
+}
+
+impl Default for Broadcast {
+    fn default() -> Self {
+        Broadcast::new()
+    }
+}
+
+//
+
+fn write_definition(bytes: &mut Vec<u8>, id: u32, body: &Body) {
+    write_varint(bytes, id as u64);
+    bytes.push(quantize_alpha(body.alpha));
+    bytes.push(body.closed as u8);
+    write_varint(bytes, body.outline.len() as u64);
+    for point in body.outline {
+        bytes.extend_from_slice(&(point.x as f32).to_le_bytes());
+        bytes.extend_from_slice(&(point.y as f32).to_le_bytes());
+    }
+}
+
+fn write_path(bytes: &mut Vec<u8>, path: &Path) {
+    bytes.push(quantize_alpha(path.alpha));
+    bytes.push(path.closed as u8);
+    write_varint(bytes, path.points.len() as u64);
+    let (mut x, mut y) = (0, 0);
+    for point in path.points.iter() {
+        let (qx, qy) = (
+            quantize(point.x, POSITION_SCALE),
+            quantize(point.y, POSITION_SCALE),
+        );
+        write_zigzag(bytes, qx - x);
+        write_zigzag(bytes, qy - y);
+        x = qx;
+        y = qy;
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while 0x80 <= value {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn write_zigzag(bytes: &mut Vec<u8>, value: i64) {
+    write_varint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn quantize(value: f64, scale: f64) -> i64 {
+    (value * scale).round() as i64
+}
+
+fn quantize_alpha(alpha: f64) -> u8 {
+    (alpha.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn quantize_placement(placement: &Placement) -> [i64; 3] {
+    [
+        quantize(placement.position.x, POSITION_SCALE),
+        quantize(placement.position.y, POSITION_SCALE),
+        quantize(placement.rotation, ROTATION_SCALE),
+    ]
+}
+
+fn dequantize_placement(placement: &[i64; 3]) -> Placement {
+    Placement {
+        position: Point::new(
+            placement[0] as f64 / POSITION_SCALE,
+            placement[1] as f64 / POSITION_SCALE,
+        ),
+        rotation: placement[2] as f64 / ROTATION_SCALE,
+    }
+}
+
+fn path_end(closed: bool) -> PathEnd {
+    if closed {
+        PathEnd::Closed
+    } else {
+        PathEnd::Open
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.offset..self.offset + length)?;
+        self.offset += length;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let mut array = [0; 4];
+        array.copy_from_slice(self.take(4)?);
+        Some(u32::from_le_bytes(array))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        let mut array = [0; 4];
+        array.copy_from_slice(self.take(4)?);
+        Some(f32::from_le_bytes(array))
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn zigzag(&mut self) -> Option<i64> {
+        let value = self.varint()?;
+        Some((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+}
+
+#[cfg(test)]
+mod test_spectate {
+    use super::*;
+    use asteroids::Controls;
+
+    const START: u32 = 32;
+
+    fn play(game: &mut Game, frame: u32) {
+        let input = match frame {
+            0..=2 => START,
+            _ if frame % 90 < 30 => 1 | 4 | 8,
+            _ if frame % 90 < 60 => 16,
+            _ => 2 | 8,
+        };
+        game.step(1.0 / 60.0, Controls::new(input));
+    }
+
+    fn assert_close(decoded: &PathList, game: &Game) {
+        let (bodies, paths) = scene(game);
+        let expected = (bodies.iter())
+            .map(|body| {
+                let points = body.placement.transform_points(body.outline);
+                (points, body.alpha, body.closed)
+            })
+            .chain(
+                paths
+                    .into_iter()
+                    .map(|path| (path.points, path.alpha, path.closed)),
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(decoded.length(), expected.len());
+        for ((points, alpha, closed), (expected_points, expected_alpha, expected_closed)) in
+            decoded.iter().zip(expected.iter())
+        {
+            assert_eq!(closed, *expected_closed);
+            assert!((alpha - expected_alpha).abs() <= 0.5 / 255.0 + 1e-6);
+            assert_eq!(points.len(), expected_points.len());
+            for (a, b) in points.iter().zip(expected_points.iter()) {
+                assert!(a.to_point().distance(b) < 0.1, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut game = Game::new();
+        let mut encoder = Encoder::new();
+        let mut broadcast = Broadcast::new();
+        let subscribers = [broadcast.subscribe(), broadcast.subscribe()];
+        let mut decoders = [Decoder::new(), Decoder::new()];
+        for frame in 0..600 {
+            play(&mut game, frame);
+            broadcast.send(&encoder.encode(&game));
+            for (subscriber, decoder) in subscribers.iter().zip(decoders.iter_mut()) {
+                let bytes = subscriber.try_recv().unwrap();
+                let list = decoder.decode(&bytes).unwrap();
+                if frame % 50 == 0 {
+                    assert_close(&list, &game);
+                }
+                assert_eq!(decoder.tick(), Some(frame));
+            }
+        }
+    }
+
+    #[test]
+    fn test_shapes_sent_once() {
+        let mut game = Game::new();
+        game.step(1.0 / 60.0, Controls::new(START));
+        for _ in 0..120 {
+            game.step(1.0 / 60.0, Controls::new(0));
+        }
+        let mut encoder = Encoder::new();
+        let first = encoder.encode(&game);
+        game.step(1.0 / 60.0, Controls::new(0));
+        let second = encoder.encode(&game);
+        assert!(4 * second.len() < first.len());
+        let mut decoder = Decoder::new();
+        decoder.decode(&first).unwrap();
+        assert_close(&decoder.decode(&second).unwrap(), &game);
+    }
+
+    #[test]
+    fn test_late_subscriber() {
+        let mut game = Game::new();
+        let mut encoder = Encoder::new();
+        let mut decoder = Decoder::new();
+        decoder.decode(&encoder.encode(&game)).unwrap();
+        play(&mut game, 10);
+        // a decoder that missed the shape definitions cannot rebuild bodies
+        assert!(Decoder::new().decode(&encoder.encode(&game)).is_none());
+        play(&mut game, 11);
+        encoder.keyframe();
+        let bytes = encoder.encode(&game);
+        assert_close(&Decoder::new().decode(&bytes).unwrap(), &game);
+        // nor does a keyframe disturb an existing decoder, which missed a frame
+        assert_close(&decoder.decode(&bytes).unwrap(), &game);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut game = Game::new();
+        let mut encoder = Encoder::new();
+        let mut decoder = Decoder::new();
+        play(&mut game, 0);
+        decoder.decode(&encoder.encode(&game)).unwrap();
+        for frame in 1..3 {
+            play(&mut game, frame);
+            if frame == 2 {
+                encoder.keyframe();
+            }
+            let bytes = encoder.encode(&game);
+            // a frame cut short anywhere leaves the decoder as it was
+            for length in 0..bytes.len() {
+                assert!(decoder.decode(&bytes[..length]).is_none());
+            }
+            assert_eq!(decoder.tick(), Some(frame - 1));
+            assert_close(&decoder.decode(&bytes).unwrap(), &game);
+            assert_eq!(decoder.tick(), Some(frame));
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        let game = Game::new();
+        let bytes = Encoder::new().encode(&game);
+        assert!(Decoder::new().decode(&bytes[..bytes.len() / 2]).is_none());
+        assert!(Decoder::new().decode(&[]).is_none());
+
+        // a path whose deltas overflow
+        let mut bytes = vec![0, 0, 0, 0, 1, 0, 0, 1, 255, 0];
+        write_varint(&mut bytes, 2);
+        for delta in [i64::MAX, 0, 1, 0].iter() {
+            write_zigzag(&mut bytes, *delta);
+        }
+        assert!(Decoder::new().decode(&bytes).is_none());
+
+        // a placement whose deltas overflow over two frames
+        let frame = |tick: u8, keyframe: bool, delta: i64| {
+            let mut bytes = vec![tick, 0, 0, 0, keyframe as u8];
+            write_varint(&mut bytes, keyframe as u64);
+            if keyframe {
+                write_varint(&mut bytes, 0);
+                bytes.extend_from_slice(&[255, 1]);
+                write_varint(&mut bytes, 0);
+            }
+            write_varint(&mut bytes, 1);
+            write_varint(&mut bytes, 0);
+            for delta in [delta, 0, 0].iter() {
+                write_zigzag(&mut bytes, *delta);
+            }
+            write_varint(&mut bytes, 0);
+            bytes
+        };
+        let mut decoder = Decoder::new();
+        assert!(decoder.decode(&frame(0, true, i64::MAX)).is_some());
+        assert!(decoder.decode(&frame(1, false, 1)).is_none());
+        assert!(decoder.decode(&frame(1, false, -1)).is_some());
+    }
+}
@@ -0,0 +1,221 @@
+use asteroids::geometry::{ngon, Point, Size};
+use asteroids::Controls;
+
+use crate::bindings::Action;
+use crate::render::{PathEnd, PathList};
+
+const STICK_RADIUS: f64 = 80.0;
+const STICK_DEAD_ZONE: f64 = 0.2; // fraction of the radius
+const STICK_THRUST: f64 = 0.5; // deflection at which thrust begins
+const BUTTON_RADIUS: f64 = 56.0;
+const BUTTON_MARGIN: f64 = 48.0;
+
+/// On-screen controls: a virtual stick, placed wherever a touch begins on the
+/// left third of the screen, and fire and shield buttons at the lower right.
+/// Touching anywhere else starts the game. Positions are in game coordinates.
+pub struct Touch {
+    fire: Point,
+    shield: Point,
+    stick_width: f64,
+    touches: Vec<(u32, Target)>,
+    enabled: bool,
+}
+
+enum Target {
+    Stick { origin: Point, position: Point },
+    Button(Action),
+    Screen,
+}
+
+impl Touch {
+    pub fn new(bounds: &Size) -> Self {
+        let offset = BUTTON_MARGIN + BUTTON_RADIUS;
+        Touch {
+            fire: Point::new(bounds.width - offset, bounds.height - offset),
+            shield: Point::new(
+                bounds.width - offset,
+                bounds.height - 3.0 * offset + BUTTON_MARGIN,
+            ),
+            stick_width: bounds.width / 3.0,
+            touches: Vec::new(),
+            enabled: false,
+        }
+    }
+
+    /// Record a touch starting, or moving if `id` is already held.
+    pub fn touch(&mut self, id: u32, position: Point) {
+        self.enabled = true;
+        if let Some((_, target)) = self.touches.iter_mut().find(|(held, _)| *held == id) {
+            if let Target::Stick {
+                position: stick, ..
+            } = target
+            {
+                *stick = position;
+            }
+            return;
+        }
+        let target = if position.distance(&self.fire) <= BUTTON_RADIUS {
+            Target::Button(Action::Fire)
+        } else if position.distance(&self.shield) <= BUTTON_RADIUS {
+            Target::Button(Action::Shield)
+        } else if position.x < self.stick_width && !self.has_stick() {
+            Target::Stick {
+                origin: position.clone(),
+                position,
+            }
+        } else {
+            Target::Screen
+        };
+        self.touches.push((id, target));
+    }
+
+    pub fn end(&mut self, id: u32) {
+        self.touches.retain(|(held, _)| *held != id);
+    }
+
+    /// Release all touches, e.g. when the page loses focus.
+    pub fn release(&mut self) {
+        self.touches.clear();
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.touches.is_empty()
+    }
+
+    /// Controls from the held touches, merged with the digital controls of
+    /// `buttons`. Turning keys apply while the stick is not aiming, and the
+    /// greater thrust of either applies.
+    pub fn controls(&self, buttons: u32) -> Controls {
+        let keys = Controls::new(buttons);
+        let mut thrust = 0.0;
+        let mut aim = None;
+        let mut input = buttons;
+        for (_, target) in self.touches.iter() {
+            match target {
+                Target::Stick { origin, position } => {
+                    let offset = position.sub(origin);
+                    let deflection = (offset.length() / STICK_RADIUS).min(1.0);
+                    if STICK_DEAD_ZONE < deflection {
+                        aim = Some(offset.angle());
+                        thrust = ((deflection - STICK_THRUST) / (1.0 - STICK_THRUST)).max(0.0);
+                    }
+                }
+                Target::Button(action) => input |= action.bit(),
+                Target::Screen => input |= Action::Start.bit(),
+            }
+        }
+        Controls::analog(keys.turn(), keys.thrust_amount().max(thrust), aim, input)
+    }
+
+    /// Outlines of the controls, once any touch has been made.
+    pub fn render(&self, list: &mut PathList) {
+        if !self.enabled {
+            return;
+        }
+        let buttons = [(&self.fire, Action::Fire), (&self.shield, Action::Shield)];
+        for (center, action) in buttons.iter() {
+            let alpha = if self.is_held(*action) { 0.6 } else { 0.25 };
+            list.push(&mut circle(center, BUTTON_RADIUS), alpha, PathEnd::Closed);
+        }
+        for (_, target) in self.touches.iter() {
+            if let Target::Stick { origin, position } = target {
+                let mut offset = position.sub(origin);
+                if STICK_RADIUS < offset.length() {
+                    offset = offset.normalize().scale(STICK_RADIUS);
+                }
+                let knob = origin.add(&offset);
+                list.push(&mut circle(origin, STICK_RADIUS), 0.25, PathEnd::Closed);
+                list.push(&mut circle(&knob, 0.4 * STICK_RADIUS), 0.6, PathEnd::Closed);
+            }
+        }
+    }
+
+    fn has_stick(&self) -> bool {
+        (self.touches.iter()).any(|(_, target)| matches!(target, Target::Stick { .. }))
+    }
+
+    fn is_held(&self, action: Action) -> bool {
+        (self.touches.iter())
+            .any(|(_, target)| matches!(target, Target::Button(held) if *held == action))
+    }
+}
+
+fn circle(center: &Point, radius: f64) -> Vec<Point> {
+    (ngon(24, radius).iter())
+        .map(|point| point.add(center))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_touch {
+    use super::*;
+
+    fn touch() -> Touch {
+        Touch::new(&Size {
+            width: 1200.0,
+            height: 900.0,
+        })
+    }
+
+    #[test]
+    fn test_stick() {
+        let mut touch = touch();
+        touch.touch(1, Point::new(200.0, 600.0));
+        let controls = touch.controls(0);
+        assert_eq!(controls.aim(), None);
+        assert_eq!(controls.thrust_amount(), 0.0);
+
+        touch.touch(1, Point::new(200.0, 500.0));
+        let controls = touch.controls(0);
+        assert_eq!(controls.aim(), Some(-std::f64::consts::FRAC_PI_2));
+        assert_eq!(controls.thrust_amount(), 1.0);
+
+        touch.touch(1, Point::new(250.0, 600.0));
+        assert_eq!(touch.controls(0).aim(), Some(0.0));
+        assert!(touch.controls(0).thrust_amount() < 0.5);
+
+        touch.end(1);
+        assert!(!touch.is_active());
+    }
+
+    #[test]
+    fn test_buttons() {
+        let mut touch = touch();
+        touch.touch(1, touch.fire.clone());
+        touch.touch(2, touch.shield.clone());
+        let controls = touch.controls(0);
+        assert!(controls.fire() && controls.shield() && !controls.start());
+
+        // moving off a button keeps it held
+        touch.touch(1, Point::new(600.0, 100.0));
+        touch.end(2);
+        let controls = touch.controls(0);
+        assert!(controls.fire() && !controls.shield());
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut touch = touch();
+        touch.touch(1, touch.fire.clone());
+        let controls = touch.controls(Action::Left.bit() | Action::Thrust.bit());
+        assert!(controls.fire() && controls.left() && controls.thrust());
+        assert_eq!(controls.turn(), -1.0);
+        assert_eq!(controls.thrust_amount(), 1.0);
+
+        // the stick aims, while thrust comes from either
+        touch.touch(2, Point::new(200.0, 600.0));
+        touch.touch(2, Point::new(250.0, 600.0));
+        let controls = touch.controls(Action::Thrust.bit());
+        assert_eq!(controls.aim(), Some(0.0));
+        assert_eq!(controls.thrust_amount(), 1.0);
+    }
+
+    #[test]
+    fn test_tap_to_start() {
+        let mut touch = touch();
+        touch.touch(7, Point::new(600.0, 450.0));
+        assert!(touch.controls(0).start());
+        touch.release();
+        assert!(!touch.controls(0).start());
+    }
+}
@@ -0,0 +1,33 @@
+// Key bindings saved from `app.bindings()`, shared by the canvas and SVG
+// front ends.
+
+const storageKey = 'bindings';
+
+// restore saved key bindings, if any
+export function loadBindings(app) {
+    const saved = window.localStorage && localStorage.getItem(storageKey);
+    if (saved && !app.load_bindings(saved)) {
+        localStorage.removeItem(storageKey);
+    }
+}
+
+// bind a key to the named action and save the bindings, returning false if
+// there is no such action
+export function bind(app, action, key) {
+    if (!app.bind(action, key)) {
+        return false;
+    }
+    saveBindings(app);
+    return true;
+}
+
+export function unbind(app, key) {
+    app.unbind(key);
+    saveBindings(app);
+}
+
+function saveBindings(app) {
+    if (window.localStorage) {
+        localStorage.setItem(storageKey, app.bindings());
+    }
+}
@@ -1,4 +1,5 @@
 import init, { App } from './wasm/app.js';
+import { loadBindings } from './bindings.js';
 
 const width = 1200;
 const height = 900;
@@ -19,6 +20,11 @@ let app;
 let memory;
 let time;
 
+let audio = null;
+let audioTime = 0;
+const audioLatency = 0.08; // seconds
+const audioBlock = 1024; // samples
+
 main();
 
 async function main() {
@@ -41,19 +47,39 @@ async function main() {
     window.addEventListener('keydown', handleKey(true));
     window.addEventListener('keyup', handleKey(false));
     screenCanvas.addEventListener('click', toggleFlat);
+    for (const type of [ 'touchstart', 'touchmove' ]) {
+        screenCanvas.addEventListener(type, handleTouch(true), { passive: false });
+    }
+    for (const type of [ 'touchend', 'touchcancel' ]) {
+        screenCanvas.addEventListener(type, handleTouch(false), { passive: false });
+    }
 
     const wasm = await init();
     memory = wasm.memory;
     app = App.new();
+    loadBindings(app);
     time = performance.now();
 
+    window.addEventListener('blur', () => app.pause());
+    document.addEventListener('visibilitychange', () => {
+        if (document.hidden) {
+            app.pause();
+        }
+    });
+
     loop(time);
 }
 
 function loop(now) {
     let dt = (now - time) / 1000;
-    app.step(dt, bitpackControls());
+    const pad = readGamepad();
+    if (pad) {
+        app.step_gamepad(dt, pad.turn, pad.thrust, pad.aim, pad.buttons);
+    } else {
+        app.step(dt, 0);
+    }
     time = now;
+    playAudio();
     draw();
     requestAnimationFrame(loop);
 
@@ -78,60 +104,88 @@ function toggleFlat() {
 
 // controls
 
-const controls = {
-    left: false,
-    right: false,
-    thrust: false,
-    fire: false,
-    shield: false,
-    start: false,
-};
-
-function bitpackControls() {
-    return 0
-        + (controls.left ? 1 : 0)
-        + (controls.right ? 2 : 0)
-        + (controls.thrust ? 4 : 0)
-        + (controls.fire ? 8 : 0)
-        + (controls.shield ? 16 : 0)
-        + (controls.start ? 32 : 0);
-}
-
 function handleKey(down) {
     return function (event) {
-        let control = keyToControl(event.key);
-        if (control !== null) {
-            controls[control] = down;
+        enableAudio();
+        if (app && app.key(event.key, down)) {
             event.preventDefault();
         }
     }
 }
 
-function keyToControl(key) {
-    switch (event.key.toLowerCase()) {
-        case 'arrowleft':
-        case 'j':
-            return 'left';
+function handleTouch(down) {
+    return function (event) {
+        event.preventDefault();
+        enableAudio();
+        if (!app) {
+            return;
+        }
+        const rect = screenCanvas.getBoundingClientRect();
+        for (const touch of event.changedTouches) {
+            if (down) {
+                const x = (touch.clientX - rect.left) * width / rect.width;
+                const y = (touch.clientY - rect.top) * height / rect.height;
+                app.touch(touch.identifier, x, y);
+            } else {
+                app.touch_end(touch.identifier);
+            }
+        }
+    }
+}
 
-        case 'arrowright':
-        case 'l':
-            return 'right';
+// gamepad
 
-        case 'arrowup':
-        case 'i':
-            return 'thrust';
+const stickDeadZone = 0.2;
+const aimDeadZone = 0.5;
 
-        case 's':
-            return 'shield';
+// read the first connected gamepad having the standard mapping, if any
+function readGamepad() {
+    const pads = navigator.getGamepads ? navigator.getGamepads() : [];
+    const pad = Array.from(pads).find((pad) => pad && pad.connected && pad.mapping === 'standard');
+    if (!pad) {
+        return null;
+    }
+    const pressed = (i) => pad.buttons[i] && pad.buttons[i].pressed;
+    const turn = Math.abs(pad.axes[0]) < stickDeadZone ? 0 : pad.axes[0];
+    const [ aimX, aimY ] = [ pad.axes[2], pad.axes[3] ];
+    const aim = Math.hypot(aimX, aimY) < aimDeadZone ? undefined : Math.atan2(aimY, aimX);
+    const thrust = Math.max(pad.buttons[7] ? pad.buttons[7].value : 0, pressed(12) ? 1 : 0);
+    const buttons = 0
+        + (pressed(0) ? 8 : 0) // A: fire
+        + (pressed(1) || pressed(5) ? 16 : 0) // B, right bumper: shield
+        + (pressed(4) ? 128 : 0) // left bumper: slow
+        + (pressed(6) ? 256 : 0) // left trigger: rewind
+        + (pressed(9) ? 32 : 0) // start
+        + (pressed(8) ? 64 : 0); // back: pause
+    return { turn, thrust, aim, buttons };
+}
 
-        case 'f':
-            return 'fire';
+// audio
 
-        case 'enter':
-            return 'start';
+function enableAudio() {
+    const AudioContext = window.AudioContext || window.webkitAudioContext;
+    if (audio || !app || !AudioContext) {
+        return;
+    }
+    audio = new AudioContext();
+    audioTime = audio.currentTime;
+    app.enable_audio(audio.sampleRate);
+}
 
-        default:
-            return null;
+function playAudio() {
+    if (!audio) {
+        return;
+    }
+    // schedule blocks of samples to stay slightly ahead of playback
+    audioTime = Math.max(audioTime, audio.currentTime);
+    while (audioTime < audio.currentTime + audioLatency) {
+        const buffer = audio.createBuffer(1, audioBlock, audio.sampleRate);
+        buffer.copyToChannel(new Float32Array(memory.buffer, app.render_audio(audioBlock), audioBlock), 0);
+        const source = audio.createBufferSource();
+        source.buffer = buffer;
+        source.connect(audio.destination);
+        source.start(audioTime);
+        audioTime += audioBlock / audio.sampleRate;
     }
 }
 
@@ -142,9 +196,9 @@ function draw() {
     const list = app.render();
     const length = list.length();
     const paths = new Uint32Array(memory.buffer, list.paths(), length * 2);
-    const alphas = new Float64Array(memory.buffer, list.alphas(), length);
+    const alphas = new Float32Array(memory.buffer, list.alphas(), length);
     const ends = new Uint8Array(memory.buffer, list.ends(), length);
-    const points = new Float64Array(memory.buffer, list.points(), list.points_length() * 2);
+    const points = new Float32Array(memory.buffer, list.points(), list.points_length() * 2);
 
     // drawing
     drawingContext.clearRect(-width, -height, 3 * width, 3 * height);
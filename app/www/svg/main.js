@@ -1,4 +1,5 @@
 import init, { App } from '../wasm/app.js';
+import { loadBindings } from '../bindings.js';
 
 const width = 1200;
 const height = 900;
@@ -39,11 +40,18 @@ async function main() {
             playPause();
         }
     });
+    for (const type of [ 'touchstart', 'touchmove' ]) {
+        screen.addEventListener(type, handleTouch(true), { passive: false });
+    }
+    for (const type of [ 'touchend', 'touchcancel' ]) {
+        screen.addEventListener(type, handleTouch(false), { passive: false });
+    }
     slider.addEventListener('input', (_) => scrub(slider.value));
 
     const wasm = await init();
     memory = wasm.memory;
     app = App.new();
+    loadBindings(app);
 
     playPause();
 }
@@ -70,7 +78,7 @@ function scrub(i) {
 }
 
 function loop(now) {
-    app.step((now - time) / 1000, bitpackControls());
+    app.step((now - time) / 1000, 0);
     time = now;
     looping = requestAnimationFrame(loop);
 
@@ -83,58 +91,30 @@ function loop(now) {
 
 // controls
 
-const controls = {
-    left: false,
-    right: false,
-    thrust: false,
-    fire: false,
-    shield: false,
-    start: false,
-};
-
-function bitpackControls() {
-    return 0
-        + (controls.left ? 1 : 0)
-        + (controls.right ? 2 : 0)
-        + (controls.thrust ? 4 : 0)
-        + (controls.fire ? 8 : 0)
-        + (controls.shield ? 16 : 0)
-        + (controls.start ? 32 : 0);
-}
-
 function handleKey(down) {
     return function (event) {
-        let control = keyToControl(event.key);
-        if (control !== null) {
-            controls[control] = down;
+        if (app && event.key !== ' ') {
+            app.key(event.key, down);
         }
     }
 }
 
-function keyToControl(key) {
-    switch (event.key) {
-        case 'ArrowLeft':
-            return 'left';
-
-        case 'ArrowRight':
-            return 'right';
-
-        case 'ArrowUp':
-            return 'thrust';
-
-        case 's':
-        case 'S':
-            return 'shield';
-
-        case 'f':
-        case 'F':
-            return 'fire';
-
-        case 'Enter':
-            return 'start';
-
-        default:
-            return null;
+function handleTouch(down) {
+    return function (event) {
+        event.preventDefault();
+        if (!app) {
+            return;
+        }
+        const rect = screen.getBoundingClientRect();
+        for (const touch of event.changedTouches) {
+            if (down) {
+                const x = (touch.clientX - rect.left) * width / rect.width;
+                const y = (touch.clientY - rect.top) * height / rect.height;
+                app.touch(touch.identifier, x, y);
+            } else {
+                app.touch_end(touch.identifier);
+            }
+        }
     }
 }
 
@@ -204,9 +184,9 @@ function screenData() {
     const list = app.render();
     const length = list.length();
     const paths = new Uint32Array(memory.buffer, list.paths(), length * 2);
-    const alphas = new Float64Array(memory.buffer, list.alphas(), length);
+    const alphas = new Float32Array(memory.buffer, list.alphas(), length);
     const ends = new Uint8Array(memory.buffer, list.ends(), length);
-    const points = new Float64Array(memory.buffer, list.points(), list.points_length() * 2);
+    const points = new Float32Array(memory.buffer, list.points(), list.points_length() * 2);
 
     // data
     const data = [];
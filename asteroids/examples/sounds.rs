@@ -0,0 +1,52 @@
+//! Render each sound effect to a WAV file, for previewing without a browser.
+//!
+//!     cargo run -p asteroids --example sounds -- [output directory]
+
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use asteroids::audio;
+use asteroids::audio::{Sound, Synth, SAMPLE_RATE};
+
+fn main() -> io::Result<()> {
+    let dir = PathBuf::from(env::args().nth(1).unwrap_or_else(|| ".".to_string()));
+
+    let sounds = [
+        ("fire", Sound::Fire),
+        ("explosion-small", Sound::Explosion { mass: 400.0 }),
+        ("explosion-medium", Sound::Explosion { mass: 2000.0 }),
+        ("explosion-large", Sound::Explosion { mass: 6000.0 }),
+        ("bounce", Sound::Bounce { speed: 300.0 }),
+        ("beat-low", Sound::Beat { high: false }),
+        ("beat-high", Sound::Beat { high: true }),
+    ];
+    for (name, sound) in sounds.iter() {
+        write(&dir, name, &audio::render_sound(*sound, SAMPLE_RATE))?;
+    }
+
+    let mut thrust = Synth::new(SAMPLE_RATE);
+    thrust.set_thrust(true);
+    write(&dir, "thrust", &render(&mut thrust, 1.0))?;
+
+    let mut shield = Synth::new(SAMPLE_RATE);
+    shield.set_shield(true);
+    write(&dir, "shield", &render(&mut shield, 1.0))?;
+
+    Ok(())
+}
+
+fn render(synth: &mut Synth, seconds: f64) -> Vec<f32> {
+    let mut buffer = vec![0.0; (seconds * SAMPLE_RATE as f64) as usize];
+    synth.render(&mut buffer);
+    buffer
+}
+
+fn write(dir: &Path, name: &str, samples: &[f32]) -> io::Result<()> {
+    let path = dir.join(format!("{}.wav", name));
+    audio::write_wav(BufWriter::new(File::create(&path)?), SAMPLE_RATE, samples)?;
+    println!("{}", path.display());
+    Ok(())
+}
@@ -3,44 +3,43 @@ use rand_pcg::Pcg32;
 use std::f64::consts::PI;
 
 use crate::blast::Blast;
-use crate::geometry::{Circle, Point, Polygon, Size};
+use crate::geometry::{ngon, Point, Polygon, Size};
 use crate::iter::EdgesCycleIterator;
 use crate::motion::{Collide, Movement, Placement};
 use crate::particle::{Dispersion, Particle};
 
+const SIMPLIFY_TOLERANCE: f64 = 0.5;
+const MIN_EDGE: f64 = 1.0;
+const CRATER_SIDES: u32 = 16;
+
 pub struct Impact {
+    pub point: Point,
+    pub speed: f64,
     pub fragments: Vec<Asteroid>,
     pub particles: Vec<Particle>,
+    pub destroyed: u32,
 }
 
+#[derive(Clone)]
 pub struct Asteroid {
     radius: f64,
     placement: Placement,
     movement: Movement,
     polygon: Vec<Point>,
     area: f64,
+    inertia: f64,
 }
 
 impl Asteroid {
     pub fn new(rng: &mut Pcg32) -> Self {
         let radius: f64 = rng.gen_range(18.0, 55.0);
-        let polygon = Asteroid::shape(rng, radius);
-        Asteroid {
-            radius,
-            placement: Placement {
-                position: Point::new(0.0, 0.0),
-                rotation: 0.0,
-            },
-            movement: Movement {
-                velocity: Point::from_polar(
-                    rng.gen_range(10.0, 80.0),
-                    rng.gen_range(0.0, 2.0 * PI),
-                ),
-                angular_velocity: rng.gen_range(-1.0, 1.0),
-            },
-            area: Polygon(&polygon).area(),
-            polygon,
-        }
+        let mut asteroid = Asteroid::from_polygon(&Asteroid::shape(rng, radius));
+        asteroid.placement.position = Point::new(0.0, 0.0);
+        asteroid.movement = Movement {
+            velocity: Point::from_polar(rng.gen_range(10.0, 80.0), rng.gen_range(0.0, 2.0 * PI)),
+            angular_velocity: rng.gen_range(-1.0, 1.0),
+        };
+        asteroid
     }
 
     fn shape(rng: &mut Pcg32, radius: f64) -> Vec<Point> {
@@ -56,12 +55,17 @@ impl Asteroid {
             .collect()
     }
 
+    /// An asteroid at rest with the given outline, positioned at its center
+    /// of mass so that it rotates about it.
     pub fn from_polygon(polygon: &Vec<Point>) -> Self {
-        let Circle { center, radius } = Circle::enclose(polygon);
+        let center = Polygon(polygon).centroid();
         let polygon = polygon
             .iter()
             .map(|point| point.sub(&center))
             .collect::<Vec<_>>();
+        let radius = (polygon.iter())
+            .map(|point| point.length())
+            .fold(0.0, f64::max);
         Asteroid {
             radius,
             placement: Placement {
@@ -70,10 +74,20 @@ impl Asteroid {
             },
             movement: Movement::zero(),
             area: Polygon(&polygon).area(),
+            inertia: Polygon(&polygon).inertia(&Point::origin()),
             polygon,
         }
     }
 
+    pub fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    /// The outline, untransformed by the placement.
+    pub fn polygon(&self) -> &Vec<Point> {
+        &self.polygon
+    }
+
     pub fn movement(&self) -> &Movement {
         &self.movement
     }
@@ -132,6 +146,7 @@ impl Asteroid {
     pub fn interact_blast(&self, rng: &mut Pcg32, blast: &Blast) -> Option<Impact> {
         if let Some(impact) = blast.impact(self) {
             let mut fragments = Vec::new();
+            let mut destroyed = 0;
             let mut particles = Dispersion::new(
                 impact.point.clone(),
                 self.movement().velocity.clone(),
@@ -141,12 +156,33 @@ impl Asteroid {
             .burst(rng, (self.radius() / 4.0).ceil() as u32);
 
             let (head, tail) = blast.endpoints();
-            for fragment_boundary in Polygon(&self.boundary()).split(&head, &tail).iter() {
-                let mut fragment = Asteroid::from_polygon(fragment_boundary);
+            let boundary = self.boundary();
+            let fragment_boundaries = Polygon(&boundary)
+                .split(&head, &tail)
+                .unwrap_or_else(|_| vec![boundary.clone()]);
+            debug_assert!(
+                (fragment_boundaries.iter()).all(|b| Polygon(b).validate().is_ok()),
+                "asteroid split into an invalid fragment"
+            );
+            for fragment_boundary in fragment_boundaries.iter() {
+                let simplified = Polygon(fragment_boundary).simplify(SIMPLIFY_TOLERANCE);
+                let simplified = Polygon(&simplified).remove_short_edges(MIN_EDGE);
+                // simplifying may fold the boundary over itself
+                let fragment_boundary = if Polygon(&simplified).validate().is_ok() {
+                    simplified
+                } else {
+                    fragment_boundary.clone()
+                };
+                let mut fragment = Asteroid::from_polygon(&fragment_boundary);
                 fragment.movement = {
                     let impact_velocity = blast.velocity().normalize().scale(impact.speed);
-                    let impact_movement =
-                        Movement::from_impulse(fragment.center(), &impact.point, &impact_velocity);
+                    let impact_movement = Movement::from_impulse(
+                        fragment.center(),
+                        &impact.point,
+                        &impact_velocity,
+                        fragment.mass(),
+                        fragment.inertia(),
+                    );
                     let outward_movement = Movement {
                         velocity: (self.center().direction_to(&fragment.center()))
                             .scale(impact.speed),
@@ -172,19 +208,57 @@ impl Asteroid {
                             .flat_map(|segment| fracture_line(&mut rng2, segment)),
                     );
                     particles.append(&mut fragment_particles);
+                    destroyed += 1;
                 } else {
                     fragments.push(fragment);
                 }
             }
 
             Some(Impact {
+                point: impact.point,
+                speed: impact.speed,
                 fragments,
                 particles,
+                destroyed,
             })
         } else {
             None
         }
     }
+
+    /// The pieces left after carving a circular crater out of the asteroid,
+    /// each drifting as the asteroid did. A crater within the asteroid would
+    /// leave a hole, so the asteroid is first split across the crater.
+    pub fn crater(&self, center: &Point, radius: f64) -> Vec<Asteroid> {
+        if self.radius + radius < self.center().distance(center) {
+            return vec![self.clone()];
+        }
+        let crater: Vec<Point> = (ngon(CRATER_SIDES, radius).iter())
+            .map(|point| point.add(center))
+            .collect();
+        let boundary = self.boundary();
+        let within = Polygon(&boundary).contains(&crater[0])
+            && (Polygon(&boundary).intersections(crater.iter().edges_cycle())).is_empty();
+        let pieces = if within {
+            let across = center.add(&Point::new(1.0, 0.0));
+            (Polygon(&boundary).split(center, &across))
+                .unwrap_or_else(|_| vec![boundary.clone()])
+                .iter()
+                .flat_map(|half| Polygon(half).difference(Polygon(&crater)))
+                .collect()
+        } else {
+            Polygon(&boundary).difference(Polygon(&crater))
+        };
+        (pieces.iter())
+            .map(|piece| Polygon(piece).remove_short_edges(MIN_EDGE))
+            .filter(|piece| Polygon(piece).validate().is_ok())
+            .map(|piece| {
+                let mut asteroid = Asteroid::from_polygon(&piece);
+                asteroid.movement = self.movement.clone();
+                asteroid
+            })
+            .collect()
+    }
 }
 
 impl Collide for Asteroid {
@@ -203,6 +277,9 @@ impl Collide for Asteroid {
     fn mass(&self) -> f64 {
         self.area
     }
+    fn inertia(&self) -> f64 {
+        self.inertia
+    }
 }
 
 fn fracture_line(rng: &mut Pcg32, segment: (Point, Point)) -> Vec<(Point, Point)> {
@@ -224,3 +301,105 @@ fn fracture_line(rng: &mut Pcg32, segment: (Point, Point)) -> Vec<(Point, Point)
         }
     }
 }
+
+#[cfg(test)]
+mod test_asteroid {
+    use super::*;
+    use crate::geometry::perimeter;
+    use rand::SeedableRng;
+
+    /// A blast that has just swept 200 pixels to `head`, travelling at
+    /// `angle`.
+    fn blast_to(head: &Point, angle: f64) -> Blast {
+        let (speed, dt) = (1000.0, 0.2);
+        let start = head.sub(&Point::from_polar(speed * dt, angle));
+        let mut blast = Blast::new(start, speed, angle);
+        blast.step(
+            dt,
+            &Size {
+                width: 1000.0,
+                height: 1000.0,
+            },
+        );
+        blast
+    }
+
+    fn square() -> Asteroid {
+        Asteroid::from_polygon(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(100.0, 0.0),
+            Point::new(100.0, 100.0),
+            Point::new(0.0, 100.0),
+        ])
+    }
+
+    #[test]
+    fn test_crater() {
+        let asteroid = square();
+        let crater_area = Polygon(&ngon(CRATER_SIDES, 10.0)).area();
+
+        // at the edge, half the crater is carved out
+        let pieces = asteroid.crater(&Point::new(50.0, 0.0), 10.0);
+        assert_eq!(pieces.len(), 1);
+        assert!((pieces[0].area() - (10_000.0 - crater_area / 2.0)).abs() < 1e-6);
+
+        // within, the asteroid is split across the crater
+        let pieces = asteroid.crater(&Point::new(50.0, 50.0), 10.0);
+        assert_eq!(pieces.len(), 2);
+        let area: f64 = pieces.iter().map(|piece| piece.area()).sum();
+        assert!((area - (10_000.0 - crater_area)).abs() < 1e-6);
+        for piece in pieces.iter() {
+            assert_eq!(Polygon(&piece.boundary()).validate(), Ok(()));
+        }
+
+        // out of reach
+        let pieces = asteroid.crater(&Point::new(200.0, 50.0), 10.0);
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].area(), 10_000.0);
+    }
+
+    #[test]
+    fn test_split() {
+        for seed in 0..50 {
+            let mut rng = Pcg32::seed_from_u64(seed);
+            let mut asteroid = Asteroid::new(&mut rng);
+            asteroid.placement.position = Point::new(500.0, 500.0);
+            let head = asteroid
+                .center()
+                .add(&Point::new(rng.gen_range(-5.0, 5.0), 0.0));
+            let blast = blast_to(&head, rng.gen_range(0.0, 2.0 * PI));
+            let impact = asteroid.interact_blast(&mut rng, &blast).unwrap();
+            assert!(2 <= impact.fragments.len() + impact.destroyed as usize);
+            for fragment in impact.fragments.iter() {
+                assert_eq!(Polygon(&fragment.boundary()).validate(), Ok(()));
+                assert!(fragment.area() < asteroid.area());
+            }
+        }
+    }
+
+    #[test]
+    fn test_simplified_fragments() {
+        for seed in 0..20 {
+            // large enough that no fragment is destroyed
+            let mut rng = Pcg32::seed_from_u64(seed);
+            let mut asteroid = Asteroid::from_polygon(&Asteroid::shape(&mut rng, 200.0));
+            asteroid.placement.position = Point::new(500.0, 500.0);
+            let blast = blast_to(asteroid.center(), rng.gen_range(0.0, 2.0 * PI));
+            let (head, tail) = blast.endpoints();
+            let mut raw: Vec<Vec<Point>> =
+                Polygon(&asteroid.boundary()).split(&head, &tail).unwrap();
+            let impact = asteroid.interact_blast(&mut rng, &blast).unwrap();
+            assert_eq!(impact.destroyed, 0);
+            assert_eq!(impact.fragments.len(), raw.len());
+            let mut fragments = impact.fragments;
+            let area = |boundary: &Vec<Point>| Polygon(boundary).area();
+            raw.sort_by(|a, b| area(a).partial_cmp(&area(b)).unwrap());
+            fragments.sort_by(|a, b| a.area().partial_cmp(&b.area()).unwrap());
+            for (fragment, raw) in fragments.iter().zip(raw.iter()) {
+                let difference = (fragment.area() - area(raw)).abs();
+                assert!(difference <= SIMPLIFY_TOLERANCE * perimeter(raw));
+                assert!(difference / area(raw) < 0.02);
+            }
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use std::f64::consts::PI;
+use std::io;
+use std::io::Write;
+
+use crate::event::GameEvent;
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+const MAX_VOICES: usize = 24;
+const DRONE_RESPONSE: f64 = 0.03; // seconds
+
+#[derive(Clone, Copy, Debug)]
+pub enum Sound {
+    Fire,
+    Explosion { mass: f64 },
+    Bounce { speed: f64 },
+    Beat { high: bool },
+}
+
+impl Sound {
+    pub fn duration(&self) -> f64 {
+        match self {
+            Sound::Fire => 0.16,
+            Sound::Explosion { mass } => (0.25 + mass.sqrt() / 60.0).min(1.8),
+            Sound::Bounce { .. } => 0.2,
+            Sound::Beat { .. } => 0.15,
+        }
+    }
+
+    fn gain(&self) -> f64 {
+        match self {
+            Sound::Fire => 0.18,
+            Sound::Explosion { mass } => (0.4 + mass.sqrt() / 60.0).min(1.2),
+            Sound::Bounce { speed } => 0.1 + (speed / 400.0).min(1.0) * 0.3,
+            Sound::Beat { .. } => 0.7,
+        }
+    }
+}
+
+struct Voice {
+    sound: Sound,
+    t: f64,
+    phase: f64,
+    filtered: f64,
+}
+
+impl Voice {
+    fn new(sound: Sound) -> Self {
+        Voice {
+            sound,
+            t: 0.0,
+            phase: 0.0,
+            filtered: 0.0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.sound.duration() <= self.t
+    }
+
+    fn sample(&mut self, rng: &mut Pcg32, dt: f64) -> f64 {
+        let u = (self.t / self.sound.duration()).min(1.0); // progress, within range [0, 1]
+        let value = match self.sound {
+            Sound::Fire => {
+                self.phase += sweep(1400.0, 300.0, u) * dt;
+                square(self.phase) * (1.0 - u).powi(2)
+            }
+            Sound::Explosion { mass } => {
+                let cutoff = 2400.0 / (1.0 + mass.sqrt() / 20.0) * (1.0 - u) + 60.0;
+                let noise = rng.gen_range(-1.0, 1.0);
+                self.filtered += one_pole(cutoff, dt) * (noise - self.filtered);
+                3.0 * self.filtered * (1.0 - u).powi(2)
+            }
+            Sound::Bounce { .. } => {
+                self.phase += sweep(260.0, 130.0, u) * dt;
+                sine(self.phase) * (1.0 - u).powi(3)
+            }
+            Sound::Beat { high } => {
+                let frequency = if high { 70.0 } else { 55.0 };
+                self.phase += sweep(frequency, 0.7 * frequency, u) * dt;
+                sine(self.phase) * (1.0 - u).powf(1.5)
+            }
+        };
+        self.t += dt;
+        value * self.sound.gain()
+    }
+}
+
+/// A continuous sound, faded in and out as it is switched on and off.
+struct Drone {
+    target: f64,
+    level: f64,
+    phase: f64,
+    filtered: f64,
+}
+
+impl Drone {
+    fn new() -> Self {
+        Drone {
+            target: 0.0,
+            level: 0.0,
+            phase: 0.0,
+            filtered: 0.0,
+        }
+    }
+
+    fn set(&mut self, on: bool) {
+        self.target = if on { 1.0 } else { 0.0 };
+    }
+
+    fn step(&mut self, dt: f64) -> f64 {
+        self.level += (self.target - self.level) * (dt / DRONE_RESPONSE).min(1.0);
+        self.level
+    }
+}
+
+pub struct Synth {
+    rng: Pcg32,
+    dt: f64,
+    voices: Vec<Voice>,
+    thrust: Drone,
+    shield: Drone,
+}
+
+impl Synth {
+    pub fn new(sample_rate: u32) -> Self {
+        Synth {
+            rng: Pcg32::seed_from_u64(1979),
+            dt: 1.0 / sample_rate.max(1) as f64,
+            voices: Vec::new(),
+            thrust: Drone::new(),
+            shield: Drone::new(),
+        }
+    }
+
+    pub fn play(&mut self, sound: Sound) {
+        if MAX_VOICES <= self.voices.len() {
+            self.voices.remove(0);
+        }
+        self.voices.push(Voice::new(sound));
+    }
+
+    pub fn handle(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::BlastFired { .. } => self.play(Sound::Fire),
+            GameEvent::AsteroidSplit { mass, .. } => self.play(Sound::Explosion { mass: *mass }),
+            GameEvent::ShieldBounce { speed, .. } => self.play(Sound::Bounce { speed: *speed }),
+            GameEvent::PlayerDestroyed { .. } => self.play(Sound::Explosion { mass: 6000.0 }),
+            GameEvent::Beat { high, .. } => self.play(Sound::Beat { high: *high }),
+            _ => (),
+        }
+    }
+
+    pub fn set_thrust(&mut self, on: bool) {
+        self.thrust.set(on);
+    }
+
+    pub fn set_shield(&mut self, on: bool) {
+        self.shield.set(on);
+    }
+
+    /// Render mono samples, within range [-1, 1], into `buffer`.
+    pub fn render(&mut self, buffer: &mut [f32]) {
+        let dt = self.dt;
+        for sample in buffer.iter_mut() {
+            let mut value = 0.0;
+            for voice in self.voices.iter_mut() {
+                value += voice.sample(&mut self.rng, dt);
+            }
+
+            let thrust = self.thrust.step(dt);
+            if 0.0 < thrust {
+                let noise = self.rng.gen_range(-1.0, 1.0);
+                let drone = &mut self.thrust;
+                drone.filtered += one_pole(320.0, dt) * (noise - drone.filtered);
+                value += 0.5 * thrust * drone.filtered;
+            }
+
+            let shield = self.shield.step(dt);
+            if 0.0 < shield {
+                let drone = &mut self.shield;
+                drone.phase += 82.0 * dt;
+                let tremolo = 0.75 + 0.25 * sine(drone.phase * 9.0 / 82.0);
+                value +=
+                    0.12 * shield * tremolo * (saw(drone.phase) + 0.5 * sine(2.0 * drone.phase));
+            }
+
+            *sample = value.tanh() as f32;
+        }
+        self.voices.retain(|voice| !voice.is_finished());
+    }
+}
+
+/// Render a single sound in isolation, such as for previewing.
+pub fn render_sound(sound: Sound, sample_rate: u32) -> Vec<f32> {
+    let mut synth = Synth::new(sample_rate);
+    synth.play(sound);
+    let mut buffer = vec![0.0; (sound.duration() * sample_rate as f64).ceil() as usize];
+    synth.render(&mut buffer);
+    buffer
+}
+
+/// Write mono samples as a 16-bit PCM WAV file.
+pub fn write_wav<W: Write>(mut writer: W, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+    let data_length = 2 * samples.len() as u32;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_length).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?; // chunk size
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&1u16.to_le_bytes())?; // channels
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(2 * sample_rate).to_le_bytes())?; // byte rate
+    writer.write_all(&2u16.to_le_bytes())?; // block align
+    writer.write_all(&16u16.to_le_bytes())?; // bits per sample
+    writer.write_all(b"data")?;
+    writer.write_all(&data_length.to_le_bytes())?;
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+// waveforms, taking phase in cycles
+
+fn sine(phase: f64) -> f64 {
+    (2.0 * PI * phase).sin()
+}
+
+fn square(phase: f64) -> f64 {
+    if phase.fract() < 0.5 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+fn saw(phase: f64) -> f64 {
+    2.0 * phase.fract() - 1.0
+}
+
+/// Exponential sweep from frequency `a` to `b`, where `t` is within range [0, 1].
+fn sweep(a: f64, b: f64, t: f64) -> f64 {
+    a * (b / a).powf(t)
+}
+
+/// Coefficient for a one-pole low-pass filter.
+fn one_pole(cutoff: f64, dt: f64) -> f64 {
+    1.0 - (-2.0 * PI * cutoff * dt).exp()
+}
+
+#[cfg(test)]
+mod test_audio {
+    use super::*;
+
+    #[test]
+    fn test_silence() {
+        let mut synth = Synth::new(SAMPLE_RATE);
+        let mut buffer = [1.0; 64];
+        synth.render(&mut buffer);
+        assert!(buffer.iter().all(|&sample| sample == 0.0));
+    }
+
+    #[test]
+    fn test_range() {
+        let samples = render_sound(Sound::Explosion { mass: 20000.0 }, SAMPLE_RATE);
+        assert!(samples
+            .iter()
+            .all(|sample| -1.0 <= *sample && *sample <= 1.0));
+        assert!(samples.iter().any(|&sample| sample != 0.0));
+    }
+
+    #[test]
+    fn test_explosion_duration() {
+        let small = render_sound(Sound::Explosion { mass: 400.0 }, SAMPLE_RATE);
+        let large = render_sound(Sound::Explosion { mass: 6000.0 }, SAMPLE_RATE);
+        assert!(small.len() < large.len());
+    }
+
+    #[test]
+    fn test_voices_finish() {
+        let mut synth = Synth::new(SAMPLE_RATE);
+        synth.play(Sound::Fire);
+        let mut buffer = vec![0.0; SAMPLE_RATE as usize];
+        synth.render(&mut buffer);
+        synth.render(&mut buffer);
+        assert!(buffer.iter().all(|&sample| sample == 0.0));
+    }
+
+    #[test]
+    fn test_wav_header() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, 8000, &[0.0, 1.0, -1.0]).unwrap();
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(&bytes[24..28], &8000u32.to_le_bytes());
+        assert_eq!(&bytes[40..44], &6u32.to_le_bytes());
+        assert_eq!(&bytes[46..48], &i16::MAX.to_le_bytes());
+    }
+}
@@ -7,11 +7,13 @@ use crate::util::Timer;
 const MAX_DISTANCE: f64 = 1200.0;
 const MASS: f64 = 200.0;
 
+#[derive(Clone)]
 pub struct Blast {
     position: Point,
     velocity: Vector,
     expiration: Timer,
     dt: f64,
+    owner: Option<usize>,
 }
 
 pub struct Impact {
@@ -26,9 +28,20 @@ impl Blast {
             velocity: Vector::from_polar(speed, angle),
             expiration: Timer::new(MAX_DISTANCE / speed),
             dt: 0.0,
+            owner: None,
         }
     }
 
+    /// Attribute this blast to a player, by index.
+    pub fn with_owner(mut self, owner: usize) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn owner(&self) -> Option<usize> {
+        self.owner
+    }
+
     pub fn step(&mut self, dt: f64, bounds: &Size) -> () {
         self.position
             .apply_velocity(&self.velocity, dt)
@@ -0,0 +1,47 @@
+use crate::geometry::Point;
+
+/// A notable moment in a game, such as for audio, analytics or achievements.
+/// Players are identified by index. Positions are in game coordinates, speeds
+/// in px / second and masses in px².
+#[derive(Clone, Debug)]
+pub enum GameEvent {
+    BlastFired {
+        player: usize,
+        position: Point,
+        speed: f64,
+    },
+    AsteroidSplit {
+        player: Option<usize>,
+        position: Point,
+        speed: f64,
+        mass: f64,
+        cleared: f64,
+        fragments: u32,
+    },
+    PlayerHit {
+        player: usize,
+        position: Point,
+        speed: f64,
+    },
+    ShieldBounce {
+        player: usize,
+        position: Point,
+        speed: f64,
+    },
+    PlayerDestroyed {
+        player: usize,
+        position: Point,
+        speed: f64,
+    },
+    PlayerFragged {
+        player: usize,
+        victim: usize,
+    },
+    LevelCleared {
+        number: u8,
+    },
+    Beat {
+        high: bool,
+        period: f64,
+    },
+}
@@ -1,10 +1,23 @@
 use std::cmp::Ordering;
 use std::f64;
 use std::f64::consts::PI;
-use std::mem;
+use std::fmt;
+
+use rand::Rng;
 
 use crate::iter::EdgesCycleIterator;
 
+pub mod boolean;
+use boolean::Operation;
+pub mod convex;
+mod enclose;
+pub mod predicates;
+use predicates::{orient2d, Orientation};
+pub mod simplify;
+pub mod triangulate;
+pub mod validity;
+use validity::InvalidPolygon;
+
 pub type Radians = f64;
 
 pub struct Size {
@@ -134,6 +147,14 @@ impl Point {
         }
     }
 
+    /// Transform as a direction or offset, ignoring translation.
+    pub fn transform_vector(&self, matrix: &Matrix) -> Self {
+        Point {
+            x: self.x * matrix.a + self.y * matrix.c,
+            y: self.x * matrix.b + self.y * matrix.d,
+        }
+    }
+
     pub fn midpoint(&self, other: &Point) -> Self {
         Point {
             x: (self.x + other.x) * 0.5,
@@ -213,6 +234,9 @@ impl Point {
 
 // Matrix
 
+/// A 2D affine transform, mapping `(x, y)` to
+/// `(a * x + c * y + tx, b * x + d * y + ty)`.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Matrix {
     a: f64,
     b: f64,
@@ -235,6 +259,96 @@ impl Matrix {
             ty: position.y,
         }
     }
+
+    pub fn identity() -> Self {
+        Matrix::scaling(1.0, 1.0)
+    }
+
+    pub fn translation(offset: &Vector) -> Self {
+        Matrix {
+            tx: offset.x,
+            ty: offset.y,
+            ..Matrix::identity()
+        }
+    }
+
+    pub fn rotation(rotation: Radians) -> Self {
+        Matrix::new(&Point::origin(), rotation, 1.0)
+    }
+
+    pub fn scaling(x: f64, y: f64) -> Self {
+        Matrix {
+            a: x,
+            b: 0.0,
+            c: 0.0,
+            d: y,
+            tx: 0.0,
+            ty: 0.0,
+        }
+    }
+
+    /// Shear by `x` along the x-axis per unit of y, and by `y` along the
+    /// y-axis per unit of x.
+    pub fn shear(x: f64, y: f64) -> Self {
+        Matrix {
+            b: y,
+            c: x,
+            ..Matrix::identity()
+        }
+    }
+
+    /// The transform applying `other` first, then `self`; e.g. a parent's
+    /// matrix multiplied by a child's gives the child's world transform.
+    pub fn multiply(&self, other: &Matrix) -> Self {
+        Matrix {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            tx: self.a * other.tx + self.c * other.ty + self.tx,
+            ty: self.b * other.tx + self.d * other.ty + self.ty,
+        }
+    }
+
+    /// The transform applying `self` first, then `other`.
+    pub fn then(&self, other: &Matrix) -> Self {
+        other.multiply(self)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// The inverse transform, unless this one collapses the plane.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Matrix {
+            a,
+            b,
+            c,
+            d,
+            tx: -(a * self.tx + c * self.ty),
+            ty: -(b * self.tx + d * self.ty),
+        })
+    }
+
+    /// The image of the origin.
+    pub fn position(&self) -> Point {
+        Point::new(self.tx, self.ty)
+    }
+
+    /// The angle to which the x-axis is rotated.
+    pub fn rotation_angle(&self) -> Radians {
+        self.b.atan2(self.a)
+    }
+
+    pub fn transform_points(&self, points: &[Point]) -> Vec<Point> {
+        (points.iter()).map(|point| point.transform(self)).collect()
+    }
 }
 
 // polygons
@@ -251,10 +365,39 @@ pub fn ngon(n: u32, radius: f64) -> Vec<Point> {
 pub struct Polygon<'a>(pub &'a Vec<Point>);
 
 impl Polygon<'_> {
-    /// Split a polygon by a line.
-    /// Assumes polygon is neither spiral nor self-intersecting.
-    pub fn split(self, a: &Point, b: &Point) -> Vec<Vec<Point>> {
-        polygons_from_split_points(rotate_split_points(&mut split_points(self.0, a, b)))
+    /// Split a polygon by a line, into the pieces on either side of it.
+    /// Assumes polygon is simple. Vertices lying exactly on the line are
+    /// treated as being on its left side, and pieces without area are dropped.
+    pub fn split(self, a: &Point, b: &Point) -> Result<Vec<Vec<Point>>, SplitError> {
+        if a == b || !is_finite(a) || !is_finite(b) {
+            return Err(SplitError::DegenerateLine);
+        }
+        let area = Polygon(self.0).area();
+        if self.0.len() < 3 || !self.0.iter().all(is_finite) || area == 0.0 {
+            return Err(SplitError::DegeneratePolygon);
+        }
+        let pieces = split_pieces(&split_nodes(self.0, a, b));
+        Ok((pieces.into_iter())
+            .map(dedup_cycle)
+            .filter(|piece| 3 <= piece.len() && f64::EPSILON * area < Polygon(piece).area())
+            .collect())
+    }
+
+    /// The union of two simple polygons, as simple polygons. Outer
+    /// boundaries are counterclockwise in a y-up frame, and where the union
+    /// encloses a hole, its boundary is given clockwise.
+    pub fn union(self, other: Polygon) -> Vec<Vec<Point>> {
+        boolean::apply(Operation::Union, self.0, other.0)
+    }
+
+    pub fn intersection(self, other: Polygon) -> Vec<Vec<Point>> {
+        boolean::apply(Operation::Intersection, self.0, other.0)
+    }
+
+    /// This polygon with `other` removed, wound as for `union`, so that the
+    /// boundary of any hole left by `other` is clockwise.
+    pub fn difference(self, other: Polygon) -> Vec<Vec<Point>> {
+        boolean::apply(Operation::Difference, self.0, other.0)
     }
 
     pub fn intersections<'a, T>(self, segments: T) -> Vec<Point>
@@ -285,85 +428,225 @@ impl Polygon<'_> {
     }
 
     pub fn area(self) -> f64 {
+        signed_area(self.0).abs()
+    }
+
+    /// Triangles covering the polygon, each counterclockwise in a y-up frame.
+    /// Assumes polygon is simple.
+    pub fn triangulate(self) -> Vec<[Point; 3]> {
+        triangulate::triangulate(self.0)
+    }
+
+    /// The center of mass, or the mean of the vertices if there is no area.
+    pub fn centroid(self) -> Point {
+        let area = signed_area(self.0);
+        if area == 0.0 {
+            return Point::mean(self.0).unwrap_or_else(Point::origin);
+        }
+        let factor = 1.0 / (6.0 * area);
         (self.0.iter())
             .edges_cycle()
-            .map(|(a, b)| (b.x - a.x) * (b.y + a.y))
+            .fold(Point::zero(), |sum, (a, b)| {
+                sum.add(&a.add(b).scale(a.cross(b)))
+            })
+            .scale(factor)
+    }
+
+    /// The polar moment of inertia about `axis`, for a uniform density of 1.
+    pub fn inertia(self, axis: &Point) -> f64 {
+        (self.0.iter())
+            .edges_cycle()
+            .map(|(a, b)| {
+                let (a, b) = (a.sub(axis), b.sub(axis));
+                a.cross(&b) * (a.dot(&a) + a.dot(&b) + b.dot(&b))
+            })
             .sum::<f64>()
             .abs()
-            / 2.0
+            / 12.0
+    }
+
+    /// Remove vertices within `tolerance` of the outline without them.
+    pub fn simplify(self, tolerance: f64) -> Vec<Point> {
+        simplify::simplify(self.0, tolerance)
+    }
+
+    pub fn remove_short_edges(self, min_length: f64) -> Vec<Point> {
+        simplify::remove_short_edges(self.0, min_length)
+    }
+
+    /// Round the corners, doubling the number of vertices each iteration.
+    pub fn smooth(self, iterations: u32) -> Vec<Point> {
+        simplify::smooth(self.0, iterations)
     }
-}
 
-// 1. Insert intersection points.
+    /// Check that the polygon is simple, as assumed by `split`.
+    pub fn validate(self) -> Result<(), InvalidPolygon> {
+        validity::validate(self.0)
+    }
+
+    pub fn winding(self) -> Orientation {
+        validity::winding(self.0)
+    }
+
+    /// Simple, counterclockwise polygons covering the same region.
+    pub fn repair(self) -> Vec<Vec<Point>> {
+        validity::repair(self.0)
+    }
+
+    pub fn aabb(self) -> Aabb {
+        Aabb::enclose(self.0)
+    }
 
-enum SplitPoint {
-    Point(Point),
-    Intersection(Point),
+    /// The nearest point where a ray from `origin` in `direction` meets the
+    /// boundary.
+    pub fn raycast(self, origin: &Point, direction: &Vector) -> Option<Hit> {
+        self.cast(Inter::RaySegment, origin, &origin.add(direction))
+    }
+
+    /// The nearest point to `a` where segment `ab` meets the boundary.
+    pub fn segment_cast(self, a: &Point, b: &Point) -> Option<Hit> {
+        self.cast(Inter::SegmentSegment, a, b)
+    }
+
+    fn cast(self, inter: Inter, a: &Point, b: &Point) -> Option<Hit> {
+        if a == b {
+            return None;
+        }
+        let direction = b.sub(a);
+        (self.0.iter())
+            .edges_cycle()
+            .filter_map(|(c, d)| {
+                intersect(inter, a, b, c, d).map(|point| {
+                    let edge = d.sub(c);
+                    let normal = Vector::new(-edge.y, edge.x).normalize();
+                    Hit {
+                        distance: point.distance(a),
+                        point,
+                        // facing back toward the caster
+                        normal: if 0.0 < normal.dot(&direction) {
+                            normal.scale(-1.0)
+                        } else {
+                            normal
+                        },
+                    }
+                })
+            })
+            .min_by(|p, q| p.distance.partial_cmp(&q.distance).unwrap())
+    }
 }
 
-fn split_points(polygon: &Vec<Point>, a: &Point, b: &Point) -> Vec<SplitPoint> {
-    polygon
-        .iter()
-        .edges_cycle()
-        .fold(Vec::new(), |mut points, edge| {
-            points.push(SplitPoint::Point(edge.0.clone()));
-            if let Some(intersection) = intersect(Inter::LineSegment, a, b, edge.0, edge.1) {
-                points.push(SplitPoint::Intersection(intersection));
-            }
-            points
-        })
+/// Where a ray or segment meets a boundary.
+#[derive(Clone, Debug)]
+pub struct Hit {
+    pub point: Point,
+    pub distance: f64,
+    pub normal: Vector,
 }
 
-// 2. Rotate split points so that intersection points are in sorted order.
-// (This is only necessary if there are more than two intersection points.)
+#[derive(Debug, PartialEq)]
+pub enum SplitError {
+    DegenerateLine,
+    DegeneratePolygon,
+}
 
-fn rotate_split_points(points: &mut Vec<SplitPoint>) -> &mut Vec<SplitPoint> {
-    let intersections: Vec<(usize, &Point)> = points
-        .iter()
-        .enumerate()
-        .filter_map(|(i, split_point)| {
-            if let SplitPoint::Intersection(point) = split_point {
-                Some((i, point))
-            } else {
-                None
-            }
-        })
-        .collect();
-    if 2 < intersections.len() {
-        let mut pairs = intersections.iter().edges_cycle();
-        let ord = pairs.next().map(|(a, b)| a.1.cmp(b.1)).unwrap();
-        let first = (pairs.find(|(a, b)| a.1.cmp(b.1) != ord).unwrap().1).0;
-        points.rotate_left(first);
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SplitError::DegenerateLine => write!(f, "split line has no direction"),
+            SplitError::DegeneratePolygon => write!(f, "polygon has no area"),
+        }
     }
-    points
 }
 
-// 3. Construct polygons.
+// 1. Insert crossing points between vertices on opposite sides of the line,
+// and pair the crossings that bound each interior segment of the line.
 
-fn polygons_from_split_points(points: &Vec<SplitPoint>) -> Vec<Vec<Point>> {
-    let mut working = Vec::new();
-    let mut waiting = Vec::new();
-    let mut completed = Vec::new();
+struct SplitNode {
+    point: Point,
+    partner: Option<usize>, // index of the paired crossing node
+}
 
-    for split_point in points.iter() {
-        match split_point {
-            SplitPoint::Point(point) => {
-                working.push(point.clone());
-            }
-            SplitPoint::Intersection(point) => {
-                working.push(point.clone());
-                waiting.push(point.clone());
-                if waiting.len() != 1 {
-                    // if waiting was not empty, then working is complete
-                    completed.push(working);
-                    working = Vec::new();
+fn split_nodes(polygon: &[Point], a: &Point, b: &Point) -> Vec<SplitNode> {
+    let direction = b.sub(a);
+    let mut nodes = Vec::with_capacity(polygon.len() + 2);
+    let mut crossings = Vec::new();
+    for (p, q) in polygon.iter().edges_cycle() {
+        nodes.push(SplitNode {
+            point: p.clone(),
+            partner: None,
+        });
+        let (side_p, side_q) = (orient2d(a, b, p), orient2d(a, b, q));
+        if (side_p < 0.0) != (side_q < 0.0) {
+            let point = p.interpolate(q, side_p / (side_p - side_q));
+            crossings.push((point.sub(a).dot(&direction), nodes.len()));
+            nodes.push(SplitNode {
+                point,
+                partner: None,
+            });
+        }
+    }
+    crossings.sort_by(|(s, _), (t, _)| s.partial_cmp(t).unwrap_or(Ordering::Equal));
+    for pair in crossings.chunks(2) {
+        if let [(_, i), (_, j)] = pair {
+            nodes[*i].partner = Some(*j);
+            nodes[*j].partner = Some(*i);
+        }
+    }
+    nodes
+}
+
+// 2. Trace each piece, following the boundary and jumping across the line at
+// each crossing.
+
+fn split_pieces(nodes: &[SplitNode]) -> Vec<Vec<Point>> {
+    let n = nodes.len();
+    let mut visited = vec![false; n];
+    let mut pieces = Vec::new();
+    for start in 0..n {
+        if visited[start] || nodes[start].partner.is_some() {
+            continue;
+        }
+        let mut piece = Vec::new();
+        let mut i = start;
+        loop {
+            visited[i] = true;
+            piece.push(nodes[i].point.clone());
+            i = match nodes[i].partner {
+                Some(j) => {
+                    piece.push(nodes[j].point.clone());
+                    (j + 1) % n
                 }
-                mem::swap(&mut working, &mut waiting);
+                None => (i + 1) % n,
+            };
+            if i == start || visited[i] {
+                break;
             }
         }
+        pieces.push(piece);
+    }
+    pieces
+}
+
+fn dedup_cycle(mut points: Vec<Point>) -> Vec<Point> {
+    points.dedup();
+    while 1 < points.len() && points.first() == points.last() {
+        points.pop();
     }
-    completed.push(working);
-    completed
+    points
+}
+
+fn is_finite(point: &Point) -> bool {
+    point.x.is_finite() && point.y.is_finite()
+}
+
+/// The area enclosed by a boundary, positive if it is counterclockwise in a
+/// y-up frame and negative if clockwise.
+fn signed_area(polygon: &[Point]) -> f64 {
+    -(polygon.iter())
+        .edges_cycle()
+        .map(|(a, b)| (b.x - a.x) * (b.y + a.y))
+        .sum::<f64>()
+        / 2.0
 }
 
 // lines
@@ -373,46 +656,64 @@ fn perpendicular_bisector(a: &Point, b: &Point) -> (Point, Point) {
     (m.clone(), Point::new(m.x + (b.y - a.y), m.y - (b.x - a.x)))
 }
 
+#[derive(Clone, Copy)]
 enum Inter {
     LineLine,
-    LineSegment,
     SegmentSegment,
+    RaySegment, // from a through b
 }
 
+/// Intersection of line or segment `ab` with line or segment `cd`, using
+/// exact orientation tests to decide whether they meet. Parallel lines and
+/// collinear segments have no single intersection, so give `None`.
 fn intersect(inter: Inter, a: &Point, b: &Point, c: &Point, d: &Point) -> Option<Point> {
-    let rx = b.x - a.x;
-    let ry = b.y - a.y;
-    let sx = d.x - c.x;
-    let sy = d.y - c.y;
-    let rs = rx * sy - sx * ry; // cross r s
-    let ex = c.x - a.x;
-    let ey = c.y - a.y;
-    let u = (ex * ry - rx * ey) / rs; // cross e r / rs
-    if rs != 0.0 {
-        match inter {
-            Inter::LineLine => Some(Point::new(c.x + u * sx, c.y + u * sy)),
-            Inter::LineSegment => {
-                if 0.0 <= u && u <= 1.0 {
-                    Some(Point::new(c.x + u * sx, c.y + u * sy))
-                } else {
-                    None
-                }
+    match inter {
+        Inter::LineLine => {
+            let r = b.sub(a);
+            let s = d.sub(c);
+            if orient2d(&Point::zero(), &r, &s) == 0.0 {
+                None
+            } else {
+                let u = c.sub(a).cross(&r) / r.cross(&s);
+                Some(c.add(&s.scale(u)))
             }
-            Inter::SegmentSegment => {
-                if 0.0 <= u && u <= 1.0 {
-                    let t = (ex * sy - sx * ey) / rs; // cross e s / rs
-                    if 0.0 <= t && t <= 1.0 {
-                        Some(Point::new(c.x + u * sx, c.y + u * sy))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
+        }
+        Inter::SegmentSegment => {
+            let side_a = orient2d(c, d, a);
+            let side_b = orient2d(c, d, b);
+            if straddles(side_a, side_b) {
+                intersect_line_segment(a, b, c, d)
+            } else {
+                None
             }
         }
-    } else {
+        Inter::RaySegment => {
+            intersect_line_segment(a, b, c, d).filter(|point| 0.0 <= point.sub(a).dot(&b.sub(a)))
+        }
+    }
+}
+
+fn intersect_line_segment(a: &Point, b: &Point, c: &Point, d: &Point) -> Option<Point> {
+    let side_c = orient2d(a, b, c);
+    let side_d = orient2d(a, b, d);
+    if !straddles(side_c, side_d) {
         None
+    } else if side_c == 0.0 {
+        Some(c.clone())
+    } else if side_d == 0.0 {
+        Some(d.clone())
+    } else {
+        Some(c.interpolate(d, side_c / (side_c - side_d)))
+    }
+}
+
+/// Whether points on sides `s` and `t` of a line, as given by `orient2d`,
+/// are on opposite sides or one is on the line, but not both.
+fn straddles(s: f64, t: f64) -> bool {
+    match (s == 0.0, t == 0.0) {
+        (true, true) => false,
+        (false, false) => (s < 0.0) != (t < 0.0),
+        _ => true,
     }
 }
 
@@ -479,6 +780,7 @@ impl Circ {
 
 // Circle
 
+#[derive(Clone, Debug)]
 pub struct Circle {
     pub center: Point,
     pub radius: f64,
@@ -487,7 +789,8 @@ pub struct Circle {
 impl Circle {
     /// Compute the smallest enclosing circle from a list of points in linear time.
     /// Based on [Emo Welzl's algorithm](https://www.inf.ethz.ch/personal/emo/PublFiles/SmallEnclDisk_LNCS555_91.pdf).
-    /// This version assumes the list is already in a random order.
+    /// This version assumes the list is already in a random order, and may
+    /// take quadratic time otherwise; see `enclose_shuffled`.
     pub fn enclose(points: &Vec<Point>) -> Self {
         let mut refs: Vec<&Point> = points.iter().collect();
         let points = refs.as_mut_slice();
@@ -495,7 +798,24 @@ impl Circle {
             0 => Circ::degenerate(&Point::origin()),
             1 => Circ::degenerate(points[0]),
             2 => Circ::circumcircle2(points[0], points[1]),
-            _ => Circle::enclose_help(Circ::enclose3(points[0], points[1], points[2]), points, 3),
+            _ => {
+                let mut circ = Circ::enclose3(points[0], points[1], points[2]);
+                let mut i = 3;
+                while i < points.len() {
+                    if circ.encloses(points[i]) {
+                        i += 1;
+                    } else {
+                        // move the point to the front, and start over
+                        points.swap(3, i);
+                        points.swap(2, 3);
+                        points.swap(1, 2);
+                        points.swap(0, 1);
+                        circ = Circ::enclose3(points[0], points[1], points[2]);
+                        i = 3;
+                    }
+                }
+                circ
+            }
         };
         Circle {
             center: circ.center,
@@ -503,19 +823,309 @@ impl Circle {
         }
     }
 
-    fn enclose_help(circ: Circ, points: &mut [&Point], i: usize) -> Circ {
-        if points.len() <= i {
-            circ
-        } else {
-            if circ.encloses(points[i]) {
-                Circle::enclose_help(circ, points, i + 1)
-            } else {
-                points.swap(3, i);
-                points.swap(2, 3);
-                points.swap(1, 2);
-                points.swap(0, 1);
-                Circle::enclose_help(Circ::enclose3(points[0], points[1], points[2]), points, 3)
-            }
+    /// The smallest enclosing circle, shuffling the points with `rng` so that
+    /// it takes expected linear time on any input.
+    pub fn enclose_shuffled<R: Rng + ?Sized>(points: &[Point], rng: &mut R) -> Self {
+        enclose::circle(points, rng)
+    }
+
+    /// The smallest circle enclosing a list of circles.
+    pub fn enclose_circles<R: Rng + ?Sized>(circles: &[Circle], rng: &mut R) -> Self {
+        enclose::circle_of_circles(circles, rng)
+    }
+}
+
+// Rectangle
+
+/// A rectangle centered at `center`, with `width` along the direction of
+/// `rotation`.
+#[derive(Clone, Debug)]
+pub struct Rectangle {
+    pub center: Point,
+    pub width: f64,
+    pub height: f64,
+    pub rotation: Radians,
+}
+
+impl Rectangle {
+    /// The rectangle of least area enclosing a list of points.
+    pub fn enclose(points: &[Point]) -> Self {
+        enclose::rectangle(points)
+    }
+
+    /// The corners, counterclockwise in a y-up frame.
+    pub fn corners(&self) -> [Point; 4] {
+        let matrix = Matrix::new(&self.center, self.rotation, 1.0);
+        let (x, y) = (self.width / 2.0, self.height / 2.0);
+        [
+            Point::new(-x, -y).transform(&matrix),
+            Point::new(x, -y).transform(&matrix),
+            Point::new(x, y).transform(&matrix),
+            Point::new(-x, y).transform(&matrix),
+        ]
+    }
+}
+
+// Aabb
+
+/// An axis-aligned bounding box. A box enclosing nothing is empty, with
+/// its minimum above its maximum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Aabb {
+            min: Point::new(f64::INFINITY, f64::INFINITY),
+            max: Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn enclose(points: &[Point]) -> Self {
+        (points.iter()).fold(Aabb::empty(), |aabb, point| Aabb {
+            min: Point::new(aabb.min.x.min(point.x), aabb.min.y.min(point.y)),
+            max: Point::new(aabb.max.x.max(point.x), aabb.max.y.max(point.y)),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max.x < self.min.x || self.max.y < self.min.y
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        Aabb {
+            min: Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    /// The overlap of two boxes, or `None` if they do not overlap.
+    pub fn intersect(&self, other: &Aabb) -> Option<Self> {
+        let aabb = Aabb {
+            min: Point::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            max: Point::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        };
+        Some(aabb).filter(|aabb| !aabb.is_empty())
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.intersect(other).is_some()
+    }
+
+    /// The box grown by `margin` on every side.
+    pub fn expand(&self, margin: f64) -> Self {
+        Aabb {
+            min: self.min.sub(&Point::new(margin, margin)),
+            max: self.max.add(&Point::new(margin, margin)),
         }
     }
 }
+
+/// A counterclockwise square with its least corner at `(x, y)`, for tests.
+#[cfg(test)]
+fn square(x: f64, y: f64, size: f64) -> Vec<Point> {
+    vec![
+        Point::new(x, y),
+        Point::new(x + size, y),
+        Point::new(x + size, y + size),
+        Point::new(x, y + size),
+    ]
+}
+
+/// The length of the boundary of a polygon, for tests.
+#[cfg(test)]
+pub(crate) fn perimeter(polygon: &[Point]) -> f64 {
+    (polygon.iter())
+        .edges_cycle()
+        .map(|(a, b)| a.distance(b))
+        .sum()
+}
+
+#[cfg(test)]
+mod test_geometry {
+    use super::*;
+
+    fn areas(pieces: &[Vec<Point>]) -> Vec<f64> {
+        let mut areas = pieces
+            .iter()
+            .map(|piece| Polygon(piece).area())
+            .collect::<Vec<_>>();
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        areas
+    }
+
+    #[test]
+    fn test_split_through_vertices() {
+        let pieces = Polygon(&square(0.0, 0.0, 10.0))
+            .split(&Point::new(-5.0, -5.0), &Point::new(20.0, 20.0))
+            .unwrap();
+        assert_eq!(areas(&pieces), vec![50.0, 50.0]);
+        assert!(pieces.iter().all(|piece| piece.len() == 3));
+    }
+
+    #[test]
+    fn test_split_along_edge() {
+        let pieces = Polygon(&square(0.0, 0.0, 10.0))
+            .split(&Point::new(10.0, -5.0), &Point::new(10.0, 20.0))
+            .unwrap();
+        assert_eq!(areas(&pieces), vec![100.0]);
+    }
+
+    #[test]
+    fn test_split_touching_vertex() {
+        let diamond = vec![
+            Point::new(0.0, -5.0),
+            Point::new(5.0, 0.0),
+            Point::new(0.0, 5.0),
+            Point::new(-5.0, 0.0),
+        ];
+        let pieces = Polygon(&diamond)
+            .split(&Point::new(5.0, -10.0), &Point::new(5.0, 10.0))
+            .unwrap();
+        assert_eq!(areas(&pieces), vec![50.0]);
+    }
+
+    #[test]
+    fn test_split_concave() {
+        // a "U" shape, cut across both arms
+        let u = vec![
+            Point::new(0.0, 0.0),
+            Point::new(30.0, 0.0),
+            Point::new(30.0, 20.0),
+            Point::new(20.0, 20.0),
+            Point::new(20.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 20.0),
+            Point::new(0.0, 20.0),
+        ];
+        let pieces = Polygon(&u)
+            .split(&Point::new(-5.0, 15.0), &Point::new(35.0, 15.0))
+            .unwrap();
+        assert_eq!(areas(&pieces), vec![50.0, 50.0, 400.0]);
+    }
+
+    #[test]
+    fn test_split_degenerate() {
+        let a = Point::new(1.0, 1.0);
+        assert_eq!(
+            Polygon(&square(0.0, 0.0, 10.0)).split(&a, &a),
+            Err(SplitError::DegenerateLine)
+        );
+        let flat = vec![a.clone(), Point::new(2.0, 2.0), Point::new(3.0, 3.0)];
+        assert_eq!(
+            Polygon(&flat).split(&Point::origin(), &Point::new(0.0, 1.0)),
+            Err(SplitError::DegeneratePolygon)
+        );
+    }
+
+    #[test]
+    fn test_intersect_endpoint() {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(10.0, 10.0);
+        let c = Point::new(5.0, 5.0);
+        let d = Point::new(10.0, 0.0);
+        assert_eq!(
+            intersect(Inter::SegmentSegment, &a, &b, &c, &d),
+            Some(c.clone())
+        );
+        assert_eq!(intersect(Inter::SegmentSegment, &a, &b, &a, &b), None);
+    }
+
+    #[test]
+    fn test_centroid_inertia() {
+        let mut square = square(0.0, 0.0, 10.0);
+        assert_eq!(Polygon(&square).centroid(), Point::new(5.0, 5.0));
+        square.reverse();
+        assert_eq!(Polygon(&square).centroid(), Point::new(5.0, 5.0));
+        // m (a^2 + a^2) / 12 about the center, plus m d^2 about a corner
+        let center = Polygon(&square).centroid();
+        assert!((Polygon(&square).inertia(&center) - 10000.0 / 6.0).abs() < 1e-9);
+        let corner = Point::origin();
+        let expected = 10000.0 / 6.0 + 100.0 * 50.0;
+        assert!((Polygon(&square).inertia(&corner) - expected).abs() < 1e-9);
+    }
+
+    fn assert_close(a: &Point, b: &Point) {
+        assert!(a.distance(b) < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_matrix_compose() {
+        let parent = Matrix::new(&Point::new(100.0, 50.0), PI / 2.0, 2.0);
+        let child =
+            Matrix::translation(&Point::new(10.0, 0.0)).multiply(&Matrix::scaling(1.0, 3.0));
+        let world = parent.multiply(&child);
+        let p = Point::new(1.0, 1.0);
+        assert_close(
+            &p.transform(&world),
+            &p.transform(&child).transform(&parent),
+        );
+        assert_eq!(child.then(&parent), world);
+        // vectors ignore translation
+        assert_close(&p.transform_vector(&world), &Point::new(-6.0, 2.0));
+    }
+
+    #[test]
+    fn test_matrix_inverse() {
+        let matrix = Matrix::shear(0.5, 0.0)
+            .then(&Matrix::rotation(1.0))
+            .then(&Matrix::translation(&Point::new(-3.0, 7.0)));
+        let p = Point::new(4.0, -2.0);
+        let inverse = matrix.inverse().unwrap();
+        assert_close(&p.transform(&matrix).transform(&inverse), &p);
+        assert_eq!(Matrix::scaling(1.0, 0.0).inverse(), None);
+    }
+
+    #[test]
+    fn test_aabb() {
+        let a = Aabb::enclose(&square(0.0, 0.0, 10.0));
+        let b = Aabb::enclose(&[Point::new(5.0, -5.0), Point::new(15.0, 5.0)]);
+        assert_eq!(
+            a.intersect(&b),
+            Some(Aabb {
+                min: Point::new(5.0, 0.0),
+                max: Point::new(10.0, 5.0),
+            })
+        );
+        assert_eq!(a.union(&b).max, Point::new(15.0, 10.0));
+        assert_eq!(a.union(&Aabb::empty()), a);
+        assert!(Aabb::enclose(&[]).is_empty());
+        assert!(a.contains(&Point::new(10.0, 10.0)));
+        let c = Aabb::enclose(&[Point::new(12.0, 0.0), Point::new(20.0, 10.0)]);
+        assert!(!a.overlaps(&c));
+        assert!(a.expand(2.0).overlaps(&c));
+    }
+
+    #[test]
+    fn test_raycast() {
+        let square = square(0.0, 0.0, 10.0);
+        let hit = Polygon(&square)
+            .raycast(&Point::new(-5.0, 5.0), &Point::new(1.0, 0.0))
+            .unwrap();
+        assert_eq!(hit.point, Point::new(0.0, 5.0));
+        assert_eq!(hit.distance, 5.0);
+        assert_eq!(hit.normal, Point::new(-1.0, 0.0));
+        // from inside, the far wall is hit from within
+        let hit = Polygon(&square)
+            .raycast(&Point::new(5.0, 5.0), &Point::new(0.0, 2.0))
+            .unwrap();
+        assert_eq!(hit.point, Point::new(5.0, 10.0));
+        assert_eq!(hit.normal, Point::new(0.0, -1.0));
+        assert!(Polygon(&square)
+            .raycast(&Point::new(-5.0, 5.0), &Point::new(-1.0, 0.0))
+            .is_none());
+        assert!(Polygon(&square)
+            .segment_cast(&Point::new(-5.0, 5.0), &Point::new(-1.0, 5.0))
+            .is_none());
+    }
+}
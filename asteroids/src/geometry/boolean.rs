@@ -0,0 +1,506 @@
+//! Boolean operations on simple polygons, after the Greiner–Hormann clipping
+//! algorithm. Where a vertex of one polygon lies on an edge of the other, the
+//! second polygon is nudged by a negligible offset, so that every intersection
+//! is a proper crossing, and the crossings are then snapped back onto the
+//! original edges.
+
+use super::convex::segments_meet;
+use super::predicates::orient2d;
+use super::validity::repair;
+use super::{dedup_cycle, intersect, signed_area, Inter, Point, Polygon};
+use crate::iter::EdgesCycleIterator;
+
+#[derive(Clone, Copy)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+const NUDGE: f64 = 1e-9; // relative to the size of the polygons
+const NUDGE_ATTEMPTS: u32 = 8;
+const SNAP: f64 = 1e-6; // relative to the size of the polygons, beyond any nudge
+
+struct Node {
+    point: Point,
+    crossing: Option<Crossing>,
+}
+
+/// A proper crossing of a subject edge and a clip edge, at fractions `t` and
+/// `u` along each.
+struct EdgeCrossing {
+    subject_edge: usize,
+    clip_edge: usize,
+    t: f64,
+    u: f64,
+    point: Point,
+}
+
+struct Crossing {
+    neighbor: usize, // index of the same point in the other polygon's nodes
+    entry: bool,
+    visited: bool,
+}
+
+/// The boundaries of the region resulting from `operation`, each simple,
+/// with outer boundaries counterclockwise in a y-up frame and the boundaries
+/// of holes clockwise. Where the region pinches to a point, its boundary is
+/// cut there into separate boundaries.
+pub fn apply(operation: Operation, subject: &[Point], clip: &[Point]) -> Vec<Vec<Point>> {
+    let scale = extent(subject).max(extent(clip));
+    orient(
+        (boundaries(operation, subject, clip).iter())
+            .flat_map(|boundary| repair(boundary))
+            .filter(|boundary| f64::EPSILON * scale * scale < signed_area(boundary).abs())
+            .collect(),
+    )
+}
+
+fn boundaries(operation: Operation, subject: &[Point], clip: &[Point]) -> Vec<Vec<Point>> {
+    if subject.len() < 3 || clip.len() < 3 {
+        return match operation {
+            Operation::Union => [subject, clip]
+                .iter()
+                .filter(|polygon| 3 <= polygon.len())
+                .map(|polygon| polygon.to_vec())
+                .collect(),
+            Operation::Intersection => Vec::new(),
+            Operation::Difference if 3 <= subject.len() => vec![subject.to_vec()],
+            Operation::Difference => Vec::new(),
+        };
+    }
+    let scale = extent(subject).max(extent(clip));
+    let mut nudged = clip.to_vec();
+    let mut offset = Point::zero();
+    for attempt in 0..NUDGE_ATTEMPTS {
+        match crossings(subject, &nudged) {
+            Some(mut crossings) => {
+                if attempt != 0 {
+                    snap(&mut crossings, subject, clip, SNAP * scale);
+                }
+                return clip_polygons(operation, subject, clip, &nudged, crossings);
+            }
+            None => {
+                // offset in an irregular direction, growing with each attempt
+                offset = offset.add(&Point::from_polar(
+                    NUDGE * scale * 2f64.powi(attempt as i32),
+                    1.0 + attempt as f64,
+                ));
+                nudged = clip.iter().map(|point| point.add(&offset)).collect();
+            }
+        }
+    }
+    match operation {
+        Operation::Union | Operation::Difference => vec![subject.to_vec()],
+        Operation::Intersection => Vec::new(),
+    }
+}
+
+/// Crossings between edges, or `None` if any edges touch without crossing.
+fn crossings(subject: &[Point], clip: &[Point]) -> Option<Vec<EdgeCrossing>> {
+    let mut list = Vec::new();
+    for (i, (a, b)) in subject.iter().edges_cycle().enumerate() {
+        for (j, (c, d)) in clip.iter().edges_cycle().enumerate() {
+            let side_a = orient2d(c, d, a);
+            let side_b = orient2d(c, d, b);
+            let side_c = orient2d(a, b, c);
+            let side_d = orient2d(a, b, d);
+            let opposite = |s: f64, t: f64| (s < 0.0 && 0.0 < t) || (t < 0.0 && 0.0 < s);
+            if opposite(side_a, side_b) && opposite(side_c, side_d) {
+                let t = side_a / (side_a - side_b);
+                let u = side_c / (side_c - side_d);
+                list.push(EdgeCrossing {
+                    subject_edge: i,
+                    clip_edge: j,
+                    t,
+                    u,
+                    point: a.interpolate(b, t),
+                });
+            } else if segments_meet(a, b, c, d) {
+                return None;
+            }
+        }
+    }
+    Some(list)
+}
+
+/// Move crossings found against the nudged clip polygon back onto the
+/// original edges: onto a vertex of either edge within `tolerance`, or else
+/// onto the crossing of the original edges.
+fn snap(crossings: &mut [EdgeCrossing], subject: &[Point], clip: &[Point], tolerance: f64) {
+    let edge = |polygon: &[Point], i: usize| {
+        (polygon[i].clone(), polygon[(i + 1) % polygon.len()].clone())
+    };
+    for crossing in crossings.iter_mut() {
+        let (a, b) = edge(subject, crossing.subject_edge);
+        let (c, d) = edge(clip, crossing.clip_edge);
+        let point = &crossing.point;
+        let vertex = [a.clone(), b.clone(), c.clone(), d.clone()]
+            .iter()
+            .filter(|vertex| vertex.distance(point) <= tolerance)
+            .min_by(|p, q| p.distance(point).partial_cmp(&q.distance(point)).unwrap())
+            .cloned();
+        crossing.point = vertex
+            .or_else(|| {
+                intersect(Inter::LineLine, &a, &b, &c, &d)
+                    .filter(|exact| exact.distance(point) <= tolerance)
+            })
+            .unwrap_or_else(|| point.clone());
+    }
+}
+
+fn extent(points: &[Point]) -> f64 {
+    (points.iter()).fold(0.0, |extent: f64, point| {
+        extent.max(point.x.abs()).max(point.y.abs())
+    })
+}
+
+/// Insert crossings between the vertices of a polygon, returning the nodes
+/// and the node index of each crossing.
+fn insert_crossings(
+    polygon: &[Point],
+    crossings: &[(usize, f64, usize)],
+) -> (Vec<Node>, Vec<usize>) {
+    let mut nodes = Vec::with_capacity(polygon.len() + crossings.len());
+    let mut indices = vec![0; crossings.len()];
+    for (i, point) in polygon.iter().enumerate() {
+        nodes.push(Node {
+            point: point.clone(),
+            crossing: None,
+        });
+        let mut on_edge = (crossings.iter())
+            .filter(|(edge, _, _)| *edge == i)
+            .collect::<Vec<_>>();
+        on_edge.sort_by(|(_, s, _), (_, t, _)| s.partial_cmp(t).unwrap());
+        for (_, _, id) in on_edge {
+            indices[*id] = nodes.len();
+            nodes.push(Node {
+                point: Point::zero(),
+                crossing: Some(Crossing {
+                    neighbor: 0,
+                    entry: false,
+                    visited: false,
+                }),
+            });
+        }
+    }
+    (nodes, indices)
+}
+
+/// Trace the boundaries of the result. The crossings and the containment
+/// of each polygon in the other are those of the `nudged` clip polygon, but
+/// the vertices are those of the original.
+fn clip_polygons(
+    operation: Operation,
+    subject: &[Point],
+    clip: &[Point],
+    nudged: &[Point],
+    crossings: Vec<EdgeCrossing>,
+) -> Vec<Vec<Point>> {
+    if crossings.is_empty() {
+        return disjoint(operation, subject, clip, nudged);
+    }
+    let (subject_crossings, clip_crossings): (Vec<_>, Vec<_>) = (crossings.iter().enumerate())
+        .map(|(id, crossing)| {
+            (
+                (crossing.subject_edge, crossing.t, id),
+                (crossing.clip_edge, crossing.u, id),
+            )
+        })
+        .unzip();
+    let (mut nodes_s, indices_s) = insert_crossings(subject, &subject_crossings);
+    let (mut nodes_c, indices_c) = insert_crossings(clip, &clip_crossings);
+    for (id, EdgeCrossing { point, .. }) in crossings.into_iter().enumerate() {
+        let (s, c) = (indices_s[id], indices_c[id]);
+        nodes_s[s].point = point.clone();
+        nodes_c[c].point = point;
+        if let Some(crossing) = &mut nodes_s[s].crossing {
+            crossing.neighbor = c;
+        }
+        if let Some(crossing) = &mut nodes_c[c].crossing {
+            crossing.neighbor = s;
+        }
+    }
+
+    let (forward_s, forward_c) = match operation {
+        Operation::Union => (false, false),
+        Operation::Intersection => (true, true),
+        Operation::Difference => (false, true),
+    };
+    let subject_polygon = subject.to_vec();
+    let clip_polygon = nudged.to_vec();
+    mark_entries(
+        &mut nodes_s,
+        forward_s != Polygon(&clip_polygon).contains(&subject[0]),
+    );
+    mark_entries(
+        &mut nodes_c,
+        forward_c != Polygon(&subject_polygon).contains(&nudged[0]),
+    );
+
+    let mut lists = [nodes_s, nodes_c];
+    let mut results = Vec::new();
+    while let Some(start) = (lists[0].iter()).position(|node| {
+        node.crossing
+            .as_ref()
+            .is_some_and(|crossing| !crossing.visited)
+    }) {
+        let mut polygon = Vec::new();
+        let (mut list, mut i) = (0, start);
+        loop {
+            let crossing = lists[list][i].crossing.as_mut().expect("crossing node");
+            crossing.visited = true;
+            let (entry, neighbor) = (crossing.entry, crossing.neighbor);
+            if let Some(crossing) = &mut lists[1 - list][neighbor].crossing {
+                crossing.visited = true;
+            }
+            let n = lists[list].len();
+            loop {
+                i = if entry { (i + 1) % n } else { (i + n - 1) % n };
+                polygon.push(lists[list][i].point.clone());
+                if lists[list][i].crossing.is_some() {
+                    break;
+                }
+            }
+            let neighbor = lists[list][i]
+                .crossing
+                .as_ref()
+                .map_or(0, |crossing| crossing.neighbor);
+            list = 1 - list;
+            i = neighbor;
+            if lists[list][i]
+                .crossing
+                .as_ref()
+                .is_some_and(|crossing| crossing.visited)
+            {
+                break;
+            }
+        }
+        // snapped crossings may coincide with their neighbors
+        let polygon = dedup_cycle(polygon);
+        if 3 <= polygon.len() {
+            results.push(polygon);
+        }
+    }
+    results
+}
+
+fn mark_entries(nodes: &mut [Node], mut entry: bool) {
+    for node in nodes.iter_mut() {
+        if let Some(crossing) = &mut node.crossing {
+            crossing.entry = entry;
+            entry = !entry;
+        }
+    }
+}
+
+/// Results for polygons whose boundaries do not cross.
+fn disjoint(
+    operation: Operation,
+    subject: &[Point],
+    clip: &[Point],
+    nudged: &[Point],
+) -> Vec<Vec<Point>> {
+    let subject_polygon = subject.to_vec();
+    let clip_polygon = clip.to_vec();
+    let subject_in_clip = Polygon(&nudged.to_vec()).contains(&subject[0]);
+    let clip_in_subject = Polygon(&subject_polygon).contains(&nudged[0]);
+    match operation {
+        Operation::Union if subject_in_clip => vec![clip_polygon],
+        Operation::Union if clip_in_subject => vec![subject_polygon],
+        Operation::Union => vec![subject_polygon, clip_polygon],
+        Operation::Intersection if subject_in_clip => vec![subject_polygon],
+        Operation::Intersection if clip_in_subject => vec![clip_polygon],
+        Operation::Intersection => Vec::new(),
+        Operation::Difference if subject_in_clip => Vec::new(),
+        Operation::Difference if clip_in_subject => vec![subject_polygon, clip_polygon],
+        Operation::Difference => vec![subject_polygon],
+    }
+}
+
+/// Wind each boundary counterclockwise, or clockwise if it bounds a hole,
+/// being within an odd number of the others.
+fn orient(mut boundaries: Vec<Vec<Point>>) -> Vec<Vec<Point>> {
+    let depths: Vec<usize> = (0..boundaries.len())
+        .map(|i| {
+            (0..boundaries.len())
+                .filter(|&j| j != i && encloses(&boundaries[j], &boundaries[i]))
+                .count()
+        })
+        .collect();
+    for (boundary, depth) in boundaries.iter_mut().zip(depths) {
+        let is_hole = depth % 2 == 1;
+        if (signed_area(boundary) < 0.0) != is_hole {
+            boundary.reverse();
+        }
+    }
+    boundaries
+}
+
+/// Whether boundary `inner` lies within boundary `outer`, which it may touch
+/// but not cross.
+fn encloses(outer: &Vec<Point>, inner: &[Point]) -> bool {
+    let on_outer = |p: &Point| {
+        (outer.iter().edges_cycle())
+            .any(|(a, b)| orient2d(a, b, p) == 0.0 && p.sub(a).dot(&p.sub(b)) <= 0.0)
+    };
+    (inner.iter())
+        .find(|point| !on_outer(point))
+        .is_some_and(|point| Polygon(outer).contains(point))
+}
+
+#[cfg(test)]
+mod test_boolean {
+    use super::*;
+    use crate::geometry::square;
+    use crate::geometry::validity::validate;
+
+    /// The area of the region, less that of its holes.
+    fn total_area(polygons: &[Vec<Point>]) -> f64 {
+        polygons.iter().map(|polygon| signed_area(polygon)).sum()
+    }
+
+    fn assert_area(polygons: &[Vec<Point>], count: usize, area: f64) {
+        assert_eq!(polygons.len(), count);
+        assert!((total_area(polygons) - area).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let a = square(0.0, 0.0, 10.0);
+        let b = square(5.0, 5.0, 10.0);
+        assert_area(&apply(Operation::Intersection, &a, &b), 1, 25.0);
+        assert_area(&apply(Operation::Union, &a, &b), 1, 175.0);
+        assert_area(&apply(Operation::Difference, &a, &b), 1, 75.0);
+        assert_area(&apply(Operation::Difference, &b, &a), 1, 75.0);
+    }
+
+    #[test]
+    fn test_disjoint_and_contained() {
+        let a = square(0.0, 0.0, 10.0);
+        let b = square(20.0, 0.0, 10.0);
+        let c = square(4.0, 4.0, 2.0);
+        assert_area(&apply(Operation::Union, &a, &b), 2, 200.0);
+        assert_area(&apply(Operation::Intersection, &a, &b), 0, 0.0);
+        assert_area(&apply(Operation::Intersection, &a, &c), 1, 4.0);
+        assert_area(&apply(Operation::Difference, &c, &a), 0, 0.0);
+        assert_area(&apply(Operation::Difference, &a, &c), 2, 96.0);
+    }
+
+    #[test]
+    fn test_winding() {
+        let a = square(0.0, 0.0, 10.0);
+        let mut b = square(5.0, 5.0, 10.0);
+        b.reverse();
+        for operation in [
+            Operation::Union,
+            Operation::Intersection,
+            Operation::Difference,
+        ]
+        .iter()
+        {
+            for polygon in apply(*operation, &b, &a).iter() {
+                assert_eq!(validate(polygon), Ok(()));
+                assert!(0.0 < signed_area(polygon));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hole() {
+        let a = square(0.0, 0.0, 10.0);
+        let c = square(4.0, 4.0, 2.0);
+        let polygons = apply(Operation::Difference, &a, &c);
+        assert_eq!(polygons, vec![a.clone(), c.iter().rev().cloned().collect()]);
+        assert!(signed_area(&polygons[1]) < 0.0);
+        for polygon in polygons.iter() {
+            assert_eq!(validate(polygon), Ok(()));
+        }
+
+        // a hole touching the outer boundary at a point
+        let notch = vec![
+            Point::new(5.0, 0.0),
+            Point::new(8.0, 5.0),
+            Point::new(2.0, 5.0),
+        ];
+        let polygons = apply(Operation::Difference, &a, &notch);
+        assert_eq!(total_area(&polygons), 85.0);
+        for polygon in polygons.iter() {
+            assert_eq!(validate(polygon), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_union_enclosing_hole() {
+        // a "C" opening to the right, closed by a bar sharing its top and
+        // bottom edges, leaving a hole inside
+        let c = vec![
+            Point::new(0.0, 0.0),
+            Point::new(30.0, 0.0),
+            Point::new(30.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 20.0),
+            Point::new(30.0, 20.0),
+            Point::new(30.0, 30.0),
+            Point::new(0.0, 30.0),
+        ];
+        let bar = vec![
+            Point::new(25.0, 0.0),
+            Point::new(35.0, 0.0),
+            Point::new(35.0, 30.0),
+            Point::new(25.0, 30.0),
+        ];
+        let mut vertices = c.clone();
+        vertices.extend(bar.iter().cloned());
+        vertices.push(Point::new(25.0, 10.0));
+        vertices.push(Point::new(25.0, 20.0));
+        for (subject, clip) in [(&c, &bar), (&bar, &c)].iter() {
+            let polygons = apply(Operation::Union, subject, clip);
+            assert_eq!(polygons.len(), 2);
+            let (outer, hole) = if 0.0 < signed_area(&polygons[0]) {
+                (&polygons[0], &polygons[1])
+            } else {
+                (&polygons[1], &polygons[0])
+            };
+            assert_eq!(signed_area(outer), 1050.0);
+            assert_eq!(signed_area(hole), -150.0);
+            // every vertex is an original vertex or an exact crossing
+            for polygon in polygons.iter() {
+                assert_eq!(validate(polygon), Ok(()));
+                assert!(polygon.iter().all(|point| vertices.contains(point)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_by_difference() {
+        // a bar across the middle cuts a square in two
+        let a = square(0.0, 0.0, 10.0);
+        let bar = vec![
+            Point::new(-5.0, 4.0),
+            Point::new(15.0, 4.0),
+            Point::new(15.0, 6.0),
+            Point::new(-5.0, 6.0),
+        ];
+        assert_area(&apply(Operation::Difference, &a, &bar), 2, 80.0);
+    }
+
+    #[test]
+    fn test_shared_edge() {
+        let a = square(0.0, 0.0, 10.0);
+        let b = square(10.0, 0.0, 10.0);
+        assert!((total_area(&apply(Operation::Union, &a, &b)) - 200.0).abs() < 1e-3);
+        assert!(total_area(&apply(Operation::Intersection, &a, &b)) < 1e-3);
+        // crossings with the nudged square are snapped back to its corners
+        let c = square(5.0, 5.0, 10.0);
+        let polygons = apply(Operation::Union, &a, &c);
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(total_area(&polygons), 175.0);
+        let polygons = apply(Operation::Union, &a, &square(5.0, 0.0, 10.0));
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(total_area(&polygons), 150.0);
+        for point in polygons[0].iter() {
+            assert!(point.x.fract() == 0.0 && point.y.fract() == 0.0);
+        }
+    }
+}
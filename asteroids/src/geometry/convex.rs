@@ -0,0 +1,267 @@
+//! Convex hulls, decomposition of simple polygons into convex parts, and
+//! collision of convex polygons by the separating axis theorem.
+
+use super::predicates::orient2d;
+use super::{signed_area, Point, Vector};
+use crate::iter::EdgesCycleIterator;
+
+/// The convex hull of a set of points, counterclockwise in a y-up frame,
+/// by Andrew's monotone chain algorithm.
+pub fn hull(points: &[Point]) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let mut hull: Vec<Point> = Vec::with_capacity(2 * sorted.len());
+    for pass in 0..2 {
+        let start = hull.len();
+        for point in sorted.iter() {
+            while start + 2 <= hull.len()
+                && orient2d(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point.clone());
+        }
+        hull.pop(); // the last point of each chain begins the other
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+pub fn is_convex(polygon: &[Point]) -> bool {
+    let mut turns = (polygon.iter())
+        .edges_cycle()
+        .zip(polygon.iter().skip(2).chain(polygon.iter().take(2)))
+        .map(|((a, b), c)| orient2d(a, b, c));
+    let mut sign = 0.0;
+    turns.all(|turn| {
+        if turn == 0.0 {
+            true
+        } else if sign == 0.0 {
+            sign = turn.signum();
+            true
+        } else {
+            turn.signum() == sign
+        }
+    })
+}
+
+/// Divide a simple polygon into convex parts, by repeatedly cutting along a
+/// diagonal from a reflex vertex.
+pub fn decompose(polygon: &[Point]) -> Vec<Vec<Point>> {
+    let mut polygon = polygon.to_vec();
+    if signed_area(&polygon) < 0.0 {
+        polygon.reverse();
+    }
+    let mut pending = vec![polygon];
+    let mut parts = Vec::new();
+    while let Some(polygon) = pending.pop() {
+        match reflex_diagonal(&polygon) {
+            Some((i, j)) => {
+                let (i, j) = (i.min(j), i.max(j));
+                pending.push(polygon[i..=j].to_vec());
+                pending.push(
+                    (polygon[j..].iter())
+                        .chain(polygon[..=i].iter())
+                        .cloned()
+                        .collect(),
+                );
+            }
+            None => parts.push(polygon),
+        }
+    }
+    parts
+}
+
+/// A diagonal from the first reflex vertex of a counterclockwise polygon to
+/// the nearest vertex it can see, if there is a reflex vertex.
+fn reflex_diagonal(polygon: &[Point]) -> Option<(usize, usize)> {
+    let n = polygon.len();
+    let reflex = (0..n).find(|&i| {
+        orient2d(
+            &polygon[(i + n - 1) % n],
+            &polygon[i],
+            &polygon[(i + 1) % n],
+        ) < 0.0
+    })?;
+    (0..n)
+        .filter(|&j| is_diagonal(polygon, reflex, j))
+        .min_by(|&j, &k| {
+            (polygon[reflex].distance_squared(&polygon[j]))
+                .partial_cmp(&polygon[reflex].distance_squared(&polygon[k]))
+                .unwrap()
+        })
+        .map(|j| (reflex, j))
+}
+
+fn is_diagonal(polygon: &[Point], i: usize, j: usize) -> bool {
+    let n = polygon.len();
+    if i == j || (i + 1) % n == j || (j + 1) % n == i {
+        return false;
+    }
+    let (a, b) = (&polygon[i], &polygon[j]);
+    in_cone(polygon, i, b)
+        && in_cone(polygon, j, a)
+        && (0..n)
+            .filter(|&k| k != i && k != j && (k + 1) % n != i && (k + 1) % n != j)
+            .all(|k| !segments_meet(a, b, &polygon[k], &polygon[(k + 1) % n]))
+}
+
+/// Whether `b` lies strictly within the interior angle at vertex `i` of a
+/// counterclockwise polygon.
+fn in_cone(polygon: &[Point], i: usize, b: &Point) -> bool {
+    let n = polygon.len();
+    let (previous, a, next) = (
+        &polygon[(i + n - 1) % n],
+        &polygon[i],
+        &polygon[(i + 1) % n],
+    );
+    if 0.0 <= orient2d(previous, a, next) {
+        0.0 < orient2d(a, b, previous) && 0.0 < orient2d(b, a, next)
+    } else {
+        !(0.0 <= orient2d(a, b, next) && 0.0 <= orient2d(b, a, previous))
+    }
+}
+
+/// Whether closed segments `ab` and `cd` share any point.
+pub(super) fn segments_meet(a: &Point, b: &Point, c: &Point, d: &Point) -> bool {
+    let sides = [
+        orient2d(c, d, a),
+        orient2d(c, d, b),
+        orient2d(a, b, c),
+        orient2d(a, b, d),
+    ];
+    if sides.iter().all(|side| *side == 0.0) {
+        let direction = b.sub(a);
+        let (s, t) = (c.sub(a).dot(&direction), d.sub(a).dot(&direction));
+        s.min(t) <= direction.dot(&direction) && 0.0 <= s.max(t)
+    } else {
+        let same_side = |s: f64, t: f64| (s < 0.0 && t < 0.0) || (0.0 < s && 0.0 < t);
+        !same_side(sides[0], sides[1]) && !same_side(sides[2], sides[3])
+    }
+}
+
+/// The overlap of two polygons, as the least distance to move the second
+/// along `normal` to separate them.
+#[derive(Clone, Debug)]
+pub struct Penetration {
+    pub depth: f64,
+    pub normal: Vector, // unit length, pointing from the first polygon toward the second
+}
+
+/// Separating axis test for two convex polygons, giving `None` when they
+/// do not overlap.
+pub fn separate(a: &[Point], b: &[Point]) -> Option<Penetration> {
+    let axes = (a.iter().edges_cycle())
+        .chain(b.iter().edges_cycle())
+        .map(|(p, q)| {
+            let edge = q.sub(p);
+            Vector::new(-edge.y, edge.x)
+        })
+        .filter(|axis| 0.0 < axis.length())
+        .map(|axis| axis.normalize());
+    let mut least: Option<Penetration> = None;
+    for axis in axes {
+        let (min_a, max_a) = project(a, &axis);
+        let (min_b, max_b) = project(b, &axis);
+        // the least distance to push b out along either direction of the axis
+        let (depth, normal) = if max_a - min_b < max_b - min_a {
+            (max_a - min_b, axis)
+        } else {
+            (max_b - min_a, axis.scale(-1.0))
+        };
+        if depth <= 0.0 {
+            return None;
+        }
+        if !matches!(&least, Some(least) if least.depth <= depth) {
+            least = Some(Penetration { depth, normal });
+        }
+    }
+    least
+}
+
+/// Separating axis test for two simple polygons, over their convex parts,
+/// giving the deepest penetration between any pair of parts.
+pub fn penetration(a: &[Point], b: &[Point]) -> Option<Penetration> {
+    let parts = |polygon: &[Point]| {
+        if is_convex(polygon) {
+            vec![polygon.to_vec()]
+        } else {
+            decompose(polygon)
+        }
+    };
+    let (parts_a, parts_b) = (parts(a), parts(b));
+    (parts_a.iter())
+        .flat_map(|a| parts_b.iter().filter_map(move |b| separate(a, b)))
+        .max_by(|p, q| p.depth.partial_cmp(&q.depth).unwrap())
+}
+
+/// The least and greatest extent of `points` along `axis`.
+pub(super) fn project(points: &[Point], axis: &Vector) -> (f64, f64) {
+    (points.iter())
+        .map(|point| point.dot(axis))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+            (min.min(x), max.max(x))
+        })
+}
+
+#[cfg(test)]
+mod test_convex {
+    use super::*;
+    use crate::geometry::{square, Polygon};
+
+    #[test]
+    fn test_hull() {
+        let mut points = square(0.0, 0.0, 10.0);
+        points.push(Point::new(5.0, 5.0));
+        points.push(Point::new(5.0, 0.0)); // collinear with an edge
+        points.push(Point::new(0.0, 0.0)); // duplicate
+        let hull = hull(&points);
+        assert_eq!(hull.len(), 4);
+        assert!(is_convex(&hull));
+        assert!(0.0 < signed_area(&hull));
+    }
+
+    #[test]
+    fn test_decompose() {
+        // an "L" shape
+        let l = vec![
+            Point::new(0.0, 0.0),
+            Point::new(20.0, 0.0),
+            Point::new(20.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 20.0),
+            Point::new(0.0, 20.0),
+        ];
+        assert!(!is_convex(&l));
+        let parts = decompose(&l);
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| is_convex(part)));
+        let area: f64 = parts.iter().map(|part| Polygon(part).area()).sum();
+        assert_eq!(area, 300.0);
+    }
+
+    #[test]
+    fn test_separate() {
+        let a = square(0.0, 0.0, 10.0);
+        let b = square(8.0, 1.0, 10.0);
+        let penetration = separate(&a, &b).unwrap();
+        assert_eq!(penetration.depth, 2.0);
+        assert_eq!(penetration.normal, Vector::new(1.0, 0.0));
+        assert!(separate(&a, &square(11.0, 0.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn test_contained() {
+        let a = square(0.0, 0.0, 10.0);
+        let b = square(2.0, 4.0, 2.0);
+        let penetration = penetration(&a, &b).unwrap();
+        assert_eq!(penetration.depth, 4.0);
+        assert_eq!(penetration.normal, Vector::new(-1.0, 0.0));
+    }
+}
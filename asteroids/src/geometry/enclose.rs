@@ -0,0 +1,331 @@
+//! Smallest enclosing shapes: circles around points or circles, by Emo
+//! Welzl's algorithm in its iterative form, and rectangles around points, by
+//! rotating calipers.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::convex::{hull, project};
+use super::{Circ, Circle, Point, Rectangle};
+
+/// The smallest circle enclosing `points`, in expected linear time for any
+/// input once shuffled. Each point failing to fit is on the boundary of the
+/// circle enclosing those before it, so the circle is rebuilt with that
+/// point fixed, then with a second fixed point, then a third.
+pub fn circle<R: Rng + ?Sized>(points: &[Point], rng: &mut R) -> Circle {
+    let mut points: Vec<&Point> = points.iter().collect();
+    points.shuffle(rng);
+    let mut circ = match points.first() {
+        Some(point) => Circ::degenerate(point),
+        None => Circ::degenerate(&Point::origin()),
+    };
+    for i in 1..points.len() {
+        if circ.encloses(points[i]) {
+            continue;
+        }
+        circ = Circ::degenerate(points[i]);
+        for j in 0..i {
+            if circ.encloses(points[j]) {
+                continue;
+            }
+            circ = Circ::circumcircle2(points[i], points[j]);
+            for k in 0..j {
+                if !circ.encloses(points[k]) {
+                    circ = Circ::circumcircle3(points[i], points[j], points[k])
+                        .unwrap_or_else(|| Circ::enclose3(points[i], points[j], points[k]));
+                }
+            }
+        }
+    }
+    Circle {
+        center: circ.center,
+        radius: circ.radius_squared.sqrt(),
+    }
+}
+
+/// The smallest circle enclosing all of `circles`, by the same algorithm as
+/// for points, where boundary points become internally tangent circles.
+pub fn circle_of_circles<R: Rng + ?Sized>(circles: &[Circle], rng: &mut R) -> Circle {
+    let mut circles: Vec<&Circle> = circles.iter().collect();
+    circles.shuffle(rng);
+    let mut enclosing = match circles.first() {
+        Some(circle) => (*circle).clone(),
+        None => Circle {
+            center: Point::origin(),
+            radius: 0.0,
+        },
+    };
+    for i in 1..circles.len() {
+        if contains(&enclosing, circles[i]) {
+            continue;
+        }
+        enclosing = circles[i].clone();
+        for j in 0..i {
+            if contains(&enclosing, circles[j]) {
+                continue;
+            }
+            enclosing = enclose2(circles[i], circles[j]);
+            for k in 0..j {
+                if !contains(&enclosing, circles[k]) {
+                    enclosing = enclose3(circles[i], circles[j], circles[k]);
+                }
+            }
+        }
+    }
+    enclosing
+}
+
+fn contains(a: &Circle, b: &Circle) -> bool {
+    // `a.center.distance(&b.center) + b.radius <= a.radius` is the ideal comparison
+    a.center.distance(&b.center) + b.radius - a.radius <= f64::EPSILON * 10.0 * (1.0 + a.radius)
+}
+
+fn enclose2(a: &Circle, b: &Circle) -> Circle {
+    if contains(a, b) {
+        return a.clone();
+    }
+    if contains(b, a) {
+        return b.clone();
+    }
+    let distance = a.center.distance(&b.center);
+    let radius = (distance + a.radius + b.radius) / 2.0;
+    Circle {
+        center: (a.center).interpolate(&b.center, (radius - a.radius) / distance),
+        radius,
+    }
+}
+
+fn enclose3(a: &Circle, b: &Circle, c: &Circle) -> Circle {
+    let pairs = [(a, b, c), (a, c, b), (b, c, a)];
+    (pairs.iter())
+        .map(|(p, q, r)| (enclose2(p, q), r))
+        .filter(|(circle, r)| contains(circle, r))
+        .map(|(circle, _)| circle)
+        .min_by(|s, t| s.radius.partial_cmp(&t.radius).unwrap())
+        .or_else(|| tangent3(a, b, c))
+        .unwrap_or_else(|| enclose2(&enclose2(a, b), c))
+}
+
+/// The circle to which all three circles are internally tangent, solving the
+/// problem of Apollonius. With `a` moved to the origin, subtracting its
+/// equation `|p|^2 = (r - ra)^2` from the others leaves two linear equations,
+/// giving the center as a linear function of `r`, which substituted back
+/// leaves a quadratic in `r`.
+fn tangent3(a: &Circle, b: &Circle, c: &Circle) -> Option<Circle> {
+    let (pb, pc) = (b.center.sub(&a.center), c.center.sub(&a.center));
+    let det = 2.0 * pb.cross(&pc);
+    if det == 0.0 {
+        return None;
+    }
+    // 2 p.x x + 2 p.y y - 2 (rp - ra) r = |p|^2 - rp^2 + ra^2, for each p
+    let (eb, ec) = (
+        pb.dot(&pb) - b.radius * b.radius + a.radius * a.radius,
+        pc.dot(&pc) - c.radius * c.radius + a.radius * a.radius,
+    );
+    let (fb, fc) = (2.0 * (b.radius - a.radius), 2.0 * (c.radius - a.radius));
+    // center = constant + r * slope, by Cramer's rule
+    let constant = Point::new(eb * pc.y - ec * pb.y, pb.x * ec - pc.x * eb).scale(1.0 / det);
+    let slope = Point::new(fb * pc.y - fc * pb.y, pb.x * fc - pc.x * fb).scale(1.0 / det);
+    // |constant + r slope|^2 = (r - ra)^2
+    let qa = slope.dot(&slope) - 1.0;
+    let qb = 2.0 * (constant.dot(&slope) + a.radius);
+    let qc = constant.dot(&constant) - a.radius * a.radius;
+    let least = a.radius.max(b.radius).max(c.radius);
+    let roots = if qa == 0.0 {
+        vec![-qc / qb]
+    } else {
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt = discriminant.sqrt();
+        vec![(-qb - sqrt) / (2.0 * qa), (-qb + sqrt) / (2.0 * qa)]
+    };
+    (roots.into_iter())
+        .filter(|r| r.is_finite() && least <= *r)
+        .min_by(|r, s| r.partial_cmp(s).unwrap())
+        .map(|radius| Circle {
+            center: a.center.add(&constant.add(&slope.scale(radius))),
+            radius,
+        })
+}
+
+/// The rectangle of least area enclosing `points`. One of its sides lies
+/// along an edge of the convex hull, so only those orientations are tried.
+pub fn rectangle(points: &[Point]) -> Rectangle {
+    let hull = hull(points);
+    let edges = (0..hull.len())
+        .map(|i| hull[(i + 1) % hull.len()].sub(&hull[i]))
+        .filter(|edge| 0.0 < edge.length());
+    let mut best: Option<(f64, Rectangle)> = None;
+    for edge in edges {
+        let u = edge.normalize();
+        let v = Point::new(-u.y, u.x);
+        let (min_u, max_u) = project(&hull, &u);
+        let (min_v, max_v) = project(&hull, &v);
+        let (width, height) = (max_u - min_u, max_v - min_v);
+        if !matches!(&best, Some((area, _)) if *area <= width * height) {
+            let center = (u.scale((min_u + max_u) / 2.0)).add(&v.scale((min_v + max_v) / 2.0));
+            best = Some((
+                width * height,
+                Rectangle {
+                    center,
+                    width,
+                    height,
+                    rotation: u.angle(),
+                },
+            ));
+        }
+    }
+    best.map_or_else(
+        || Rectangle {
+            center: hull.first().cloned().unwrap_or_else(Point::origin),
+            width: 0.0,
+            height: 0.0,
+            rotation: 0.0,
+        },
+        |(_, rectangle)| rectangle,
+    )
+}
+
+#[cfg(test)]
+mod test_enclose {
+    use super::*;
+    use crate::geometry::{Matrix, Polygon, Radians};
+    use rand::SeedableRng;
+    use rand_pcg::Pcg32;
+
+    fn random_points(rng: &mut Pcg32, n: usize) -> Vec<Point> {
+        (0..n)
+            .map(|_| Point::new(rng.gen_range(-100.0, 100.0), rng.gen_range(-100.0, 100.0)))
+            .collect()
+    }
+
+    fn encloses(circle: &Circle, points: &[Point]) -> bool {
+        (points.iter()).all(|point| circle.center.distance(point) <= circle.radius * (1.0 + 1e-9))
+    }
+
+    /// The least circle through 2 or 3 of the points that encloses them all.
+    fn brute_force(points: &[Point]) -> f64 {
+        let n = points.len();
+        let mut candidates = Vec::new();
+        for i in 0..n {
+            for j in i + 1..n {
+                candidates.push(Circ::circumcircle2(&points[i], &points[j]));
+                for k in j + 1..n {
+                    candidates.extend(Circ::circumcircle3(&points[i], &points[j], &points[k]));
+                }
+            }
+        }
+        (candidates.into_iter())
+            .map(|circ| Circle {
+                center: circ.center,
+                radius: circ.radius_squared.sqrt(),
+            })
+            .filter(|circle| encloses(circle, points))
+            .map(|circle| circle.radius)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// The least radius of a circle enclosing `circles`, found numerically.
+    /// The farthest reach of any circle from a center is a convex function
+    /// of it, minimized over `y` for each `x` and then over `x`, each by
+    /// ternary search within the bounds of the centers.
+    fn minimize_radius(circles: &[Circle]) -> f64 {
+        let reach = |center: &Point| {
+            (circles.iter())
+                .map(|circle| center.distance(&circle.center) + circle.radius)
+                .fold(0.0, f64::max)
+        };
+        let centers: Vec<Point> = circles.iter().map(|circle| circle.center.clone()).collect();
+        let (min_x, max_x) = project(&centers, &Point::new(1.0, 0.0));
+        let (min_y, max_y) = project(&centers, &Point::new(0.0, 1.0));
+        let least_over_y = |x: f64| ternary_search(min_y, max_y, |y: f64| reach(&Point::new(x, y)));
+        ternary_search(min_x, max_x, least_over_y)
+    }
+
+    /// The least value of a convex function between `lo` and `hi`.
+    fn ternary_search<F: Fn(f64) -> f64>(mut lo: f64, mut hi: f64, f: F) -> f64 {
+        for _ in 0..100 {
+            let (a, b) = (lo + (hi - lo) / 3.0, hi - (hi - lo) / 3.0);
+            if f(a) < f(b) {
+                hi = b;
+            } else {
+                lo = a;
+            }
+        }
+        f((lo + hi) / 2.0)
+    }
+
+    #[test]
+    fn test_circle() {
+        let mut rng = Pcg32::seed_from_u64(1);
+        for n in 2..12 {
+            for _ in 0..20 {
+                let points = random_points(&mut rng, n);
+                let circle = circle(&points, &mut rng);
+                assert!(encloses(&circle, &points));
+                assert!((circle.radius - brute_force(&points)).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_circle_adversarial() {
+        // sorted along a line, the worst order for the unshuffled algorithm
+        let points: Vec<Point> = (0..100_000).map(|i| Point::new(i as f64, 0.0)).collect();
+        let circle = circle(&points, &mut Pcg32::seed_from_u64(2));
+        assert!((circle.radius - 49_999.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_circle_of_circles() {
+        let mut rng = Pcg32::seed_from_u64(3);
+        for n in 1..8 {
+            for _ in 0..20 {
+                let circles: Vec<Circle> = (random_points(&mut rng, n).into_iter())
+                    .map(|center| Circle {
+                        center,
+                        radius: rng.gen_range(0.0, 30.0),
+                    })
+                    .collect();
+                let enclosing = circle_of_circles(&circles, &mut rng);
+                assert!(
+                    (circles.iter()).all(|circle| enclosing.center.distance(&circle.center)
+                        + circle.radius
+                        <= enclosing.radius + 1e-6)
+                );
+                let least = minimize_radius(&circles);
+                assert!((enclosing.radius - least).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rectangle() {
+        let mut rng = Pcg32::seed_from_u64(4);
+        for n in 3..12 {
+            let points = random_points(&mut rng, n);
+            let rectangle = rectangle(&points);
+            let corners = rectangle.corners();
+            let area = Polygon(&corners.to_vec()).area();
+            assert!((area - rectangle.width * rectangle.height).abs() < 1e-6);
+            // every point lies within, allowing for rounding
+            for point in points.iter() {
+                let local = (point.sub(&rectangle.center))
+                    .transform(&Matrix::rotation(-rectangle.rotation));
+                assert!(local.x.abs() <= rectangle.width / 2.0 + 1e-9);
+                assert!(local.y.abs() <= rectangle.height / 2.0 + 1e-9);
+            }
+            // no sampled orientation does better
+            for i in 0..360 {
+                let angle = i as Radians * std::f64::consts::PI / 360.0;
+                let u = Point::from_polar(1.0, angle);
+                let v = Point::new(-u.y, u.x);
+                let (min_u, max_u) = project(&points, &u);
+                let (min_v, max_v) = project(&points, &v);
+                assert!(area <= (max_u - min_u) * (max_v - min_v) + 1e-6);
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Orientation tests that are exact in sign, after Jonathan Shewchuk's
+//! [adaptive precision predicates](https://www.cs.cmu.edu/~quake/robust.html).
+//! A fast floating-point evaluation is used when its error bound proves the
+//! sign correct, falling back to exact arithmetic on expansions otherwise.
+
+use super::Point;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+    Collinear,
+}
+
+const EPSILON: f64 = f64::EPSILON / 2.0; // unit roundoff
+const CCW_ERROR_BOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+
+/// Twice the signed area of triangle `abc`: positive when `abc` turns
+/// counterclockwise in a y-up frame, negative when clockwise, and zero when
+/// collinear. The sign is always exact, while the magnitude is approximate.
+pub fn orient2d(a: &Point, b: &Point, c: &Point) -> f64 {
+    let left = (a.x - c.x) * (b.y - c.y);
+    let right = (a.y - c.y) * (b.x - c.x);
+    let det = left - right;
+    let bound = CCW_ERROR_BOUND * (left.abs() + right.abs());
+    if bound <= det.abs() && det != 0.0 {
+        det
+    } else {
+        orient2d_exact(a, b, c)
+    }
+}
+
+pub fn orientation(a: &Point, b: &Point, c: &Point) -> Orientation {
+    let det = orient2d(a, b, c);
+    if 0.0 < det {
+        Orientation::CounterClockwise
+    } else if det < 0.0 {
+        Orientation::Clockwise
+    } else {
+        Orientation::Collinear
+    }
+}
+
+fn orient2d_exact(a: &Point, b: &Point, c: &Point) -> f64 {
+    // expanded so that each term is a product of input coordinates
+    let products = [
+        two_product(a.x, b.y),
+        two_product(-a.x, c.y),
+        two_product(-c.x, b.y),
+        two_product(-a.y, b.x),
+        two_product(a.y, c.x),
+        two_product(c.y, b.x),
+    ];
+    let mut expansion = Vec::with_capacity(12);
+    for (product, error) in products.iter() {
+        grow_expansion(&mut expansion, *error);
+        grow_expansion(&mut expansion, *product);
+    }
+    // components are nonoverlapping and increasing in magnitude, so the last
+    // one determines the sign
+    expansion
+        .iter()
+        .sum::<f64>()
+        .abs()
+        .copysign(*expansion.last().unwrap_or(&0.0))
+}
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    (x, (a - a_virtual) + (b - b_virtual))
+}
+
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    (x, a.mul_add(b, -x))
+}
+
+/// Add `b` to an expansion, eliminating zero components.
+fn grow_expansion(expansion: &mut Vec<f64>, b: f64) {
+    let mut q = b;
+    let mut result = Vec::with_capacity(expansion.len() + 1);
+    for component in expansion.iter() {
+        let (sum, error) = two_sum(q, *component);
+        q = sum;
+        if error != 0.0 {
+            result.push(error);
+        }
+    }
+    if q != 0.0 {
+        result.push(q);
+    }
+    *expansion = result;
+}
+
+#[cfg(test)]
+mod test_predicates {
+    use super::*;
+
+    #[test]
+    fn test_near_collinear() {
+        let ulp = 0.5 * f64::EPSILON; // spacing of floats just above 0.5
+        let b = Point::new(12.0, 12.0);
+        let c = Point::new(24.0, 24.0);
+        for i in 0..32 {
+            for j in 0..32 {
+                let a = Point::new(0.5 + i as f64 * ulp, 0.5 + j as f64 * ulp);
+                let expected = match i.cmp(&j) {
+                    std::cmp::Ordering::Greater => Orientation::Clockwise,
+                    std::cmp::Ordering::Less => Orientation::CounterClockwise,
+                    std::cmp::Ordering::Equal => Orientation::Collinear,
+                };
+                assert_eq!(orientation(&a, &b, &c), expected);
+                assert_eq!(orientation(&b, &c, &a), expected);
+                assert_eq!(orientation(&c, &a, &b), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fast_path() {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(4.0, 0.0);
+        let c = Point::new(0.0, 3.0);
+        assert_eq!(orient2d(&a, &b, &c), 12.0);
+        assert_eq!(orient2d(&a, &c, &b), -12.0);
+    }
+}
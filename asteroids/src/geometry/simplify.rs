@@ -0,0 +1,155 @@
+//! Reducing and smoothing the vertices of closed polygons.
+
+use super::Point;
+
+/// Remove vertices lying within `tolerance` of the outline formed without
+/// them, by the Ramer–Douglas–Peucker algorithm. The polygon is divided into
+/// two chains at its first vertex and the vertex farthest from it, and each
+/// chain is simplified separately. At least 3 vertices are kept.
+pub fn simplify(polygon: &[Point], tolerance: f64) -> Vec<Point> {
+    if polygon.len() <= 3 {
+        return polygon.to_vec();
+    }
+    let far = (1..polygon.len())
+        .max_by(|&i, &j| {
+            (polygon[0].distance_squared(&polygon[i]))
+                .partial_cmp(&polygon[0].distance_squared(&polygon[j]))
+                .unwrap()
+        })
+        .unwrap_or(0);
+    let mut keep = vec![false; polygon.len()];
+    keep[0] = true;
+    keep[far] = true;
+    mark(polygon, 0, far, tolerance, &mut keep);
+    mark(polygon, far, polygon.len(), tolerance, &mut keep);
+    let simplified: Vec<Point> = (polygon.iter())
+        .zip(keep.iter())
+        .filter(|(_, keep)| **keep)
+        .map(|(point, _)| point.clone())
+        .collect();
+    if simplified.len() < 3 {
+        polygon.to_vec()
+    } else {
+        simplified
+    }
+}
+
+/// Mark the vertices to keep strictly between `start` and `end`, where an
+/// `end` equal to the length wraps to the first vertex.
+fn mark(polygon: &[Point], start: usize, end: usize, tolerance: f64, keep: &mut [bool]) {
+    let a = &polygon[start];
+    let b = &polygon[end % polygon.len()];
+    let farthest = (start + 1..end)
+        .map(|i| (i, deviation(&polygon[i], a, b)))
+        .max_by(|(_, s), (_, t)| s.partial_cmp(t).unwrap());
+    if let Some((i, distance)) = farthest {
+        if tolerance < distance {
+            keep[i] = true;
+            mark(polygon, start, i, tolerance, keep);
+            mark(polygon, i, end, tolerance, keep);
+        }
+    }
+}
+
+/// Distance from `p` to segment `ab`.
+fn deviation(p: &Point, a: &Point, b: &Point) -> f64 {
+    let ab = b.sub(a);
+    let length_squared = ab.dot(&ab);
+    if length_squared == 0.0 {
+        return p.distance(a);
+    }
+    let t = (p.sub(a).dot(&ab) / length_squared).clamp(0.0, 1.0);
+    p.distance(&a.add(&ab.scale(t)))
+}
+
+/// Merge the ends of edges shorter than `min_length`, keeping at least 3
+/// vertices.
+pub fn remove_short_edges(polygon: &[Point], min_length: f64) -> Vec<Point> {
+    let mut result: Vec<Point> = Vec::with_capacity(polygon.len());
+    for point in polygon.iter() {
+        match result.last() {
+            Some(last) if last.distance(point) < min_length => {}
+            _ => result.push(point.clone()),
+        }
+    }
+    // the closing edge
+    while 1 < result.len() && result[result.len() - 1].distance(&result[0]) < min_length {
+        result.pop();
+    }
+    if result.len() < 3 {
+        polygon.to_vec()
+    } else {
+        result
+    }
+}
+
+/// Round the corners by Chaikin's algorithm, replacing each edge with points
+/// a quarter and three quarters along it, `iterations` times.
+pub fn smooth(polygon: &[Point], iterations: u32) -> Vec<Point> {
+    let mut result = polygon.to_vec();
+    for _ in 0..iterations {
+        let n = result.len();
+        result = (0..n)
+            .flat_map(|i| {
+                let (a, b) = (&result[i], &result[(i + 1) % n]);
+                vec![a.interpolate(b, 0.25), a.interpolate(b, 0.75)]
+            })
+            .collect();
+    }
+    result
+}
+
+#[cfg(test)]
+mod test_simplify {
+    use super::*;
+    use crate::geometry::{ngon, perimeter, Polygon};
+
+    #[test]
+    fn test_simplify() {
+        // a square with extra vertices along its edges, slightly displaced
+        let mut polygon = Vec::new();
+        for (i, corner) in ngon(4, 100.0).iter().enumerate() {
+            let next = Point::from_polar(100.0, (i + 1) as f64 * std::f64::consts::FRAC_PI_2);
+            for j in 0..10 {
+                let jitter = if j % 2 == 0 { 0.1 } else { -0.1 };
+                let point = corner.interpolate(&next, j as f64 / 10.0);
+                polygon.push(point.add(&Point::new(jitter, jitter)));
+            }
+        }
+        let tolerance = 0.5;
+        let simplified = simplify(&polygon, tolerance);
+        assert_eq!(simplified.len(), 4);
+        let area = Polygon(&polygon).area();
+        let difference = (Polygon(&simplified).area() - area).abs();
+        assert!(difference <= tolerance * perimeter(&polygon));
+        assert!(difference / area < 0.01);
+    }
+
+    #[test]
+    fn test_remove_short_edges() {
+        let polygon = vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.2, 0.1),
+            Point::new(10.0, 10.0),
+            Point::new(0.0, 10.0),
+            Point::new(0.0, 0.1),
+        ];
+        let cleaned = remove_short_edges(&polygon, 1.0);
+        assert_eq!(cleaned.len(), 4);
+        let area = Polygon(&polygon).area();
+        assert!((Polygon(&cleaned).area() - area).abs() / area < 0.01);
+        // never fewer than 3 vertices
+        assert_eq!(remove_short_edges(&polygon, 100.0), polygon);
+    }
+
+    #[test]
+    fn test_smooth() {
+        let square = ngon(4, 100.0);
+        let smoothed = smooth(&square, 2);
+        assert_eq!(smoothed.len(), 16);
+        let area = Polygon(&square).area();
+        let smoothed_area = Polygon(&smoothed).area();
+        assert!(smoothed_area < area && 0.8 * area < smoothed_area);
+    }
+}
@@ -0,0 +1,85 @@
+//! Triangulation of simple polygons by ear clipping.
+
+use super::predicates::orient2d;
+use super::{signed_area, Point};
+
+/// Divide a simple polygon into `n - 2` triangles, each counterclockwise in
+/// a y-up frame, by repeatedly clipping an ear: a convex vertex whose
+/// triangle with its neighbors contains no other vertex.
+pub fn triangulate(polygon: &[Point]) -> Vec<[Point; 3]> {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    if signed_area(polygon) < 0.0 {
+        remaining.reverse();
+    }
+    let mut triangles = Vec::with_capacity(polygon.len().saturating_sub(2));
+    while 3 <= remaining.len() {
+        let n = remaining.len();
+        let corners = |i: usize| {
+            (
+                &polygon[remaining[(i + n - 1) % n]],
+                &polygon[remaining[i]],
+                &polygon[remaining[(i + 1) % n]],
+            )
+        };
+        let is_ear = |i: usize| {
+            let (a, b, c) = corners(i);
+            0.0 < orient2d(a, b, c)
+                && !(remaining.iter())
+                    .map(|&j| &polygon[j])
+                    .filter(|p| *p != a && *p != b && *p != c)
+                    .any(|p| in_triangle(a, b, c, p))
+        };
+        // a degenerate polygon may have no ear, in which case clipping any
+        // vertex still makes progress
+        let i = (0..n).find(|&i| is_ear(i)).unwrap_or(0);
+        let (a, b, c) = corners(i);
+        if 0.0 < orient2d(a, b, c) {
+            triangles.push([a.clone(), b.clone(), c.clone()]);
+        }
+        remaining.remove(i);
+    }
+    triangles
+}
+
+/// Whether `p` lies within or on the counterclockwise triangle `abc`.
+fn in_triangle(a: &Point, b: &Point, c: &Point, p: &Point) -> bool {
+    0.0 <= orient2d(a, b, p) && 0.0 <= orient2d(b, c, p) && 0.0 <= orient2d(c, a, p)
+}
+
+#[cfg(test)]
+mod test_triangulate {
+    use super::*;
+    use crate::geometry::Polygon;
+
+    #[test]
+    fn test_triangulate() {
+        // an "L" shape, clockwise
+        let mut l = vec![
+            Point::new(0.0, 0.0),
+            Point::new(20.0, 0.0),
+            Point::new(20.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 20.0),
+            Point::new(0.0, 20.0),
+        ];
+        l.reverse();
+        let triangles = triangulate(&l);
+        assert_eq!(triangles.len(), 4);
+        assert!((triangles.iter()).all(|[a, b, c]| 0.0 < orient2d(a, b, c)));
+        let area: f64 = (triangles.iter())
+            .map(|triangle| Polygon(&triangle.to_vec()).area())
+            .sum();
+        assert_eq!(area, 300.0);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let line = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+        ];
+        assert!(triangulate(&line).is_empty());
+        assert!(triangulate(&line[..2]).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Checking that polygons are simple, and repairing those that are not.
+
+use std::fmt;
+
+use super::convex::segments_meet;
+use super::predicates::{orient2d, Orientation};
+use super::{dedup_cycle, intersect, is_finite, signed_area, Inter, Point};
+
+#[derive(Debug, PartialEq)]
+pub enum InvalidPolygon {
+    TooFewVertices,
+    NonFinite,
+    DuplicateVertex,
+    ZeroArea,
+    SelfIntersecting,
+}
+
+impl fmt::Display for InvalidPolygon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidPolygon::TooFewVertices => write!(f, "polygon has fewer than 3 vertices"),
+            InvalidPolygon::NonFinite => write!(f, "polygon has a non-finite coordinate"),
+            InvalidPolygon::DuplicateVertex => write!(f, "polygon repeats a vertex"),
+            InvalidPolygon::ZeroArea => write!(f, "polygon has no area"),
+            InvalidPolygon::SelfIntersecting => write!(f, "polygon boundary meets itself"),
+        }
+    }
+}
+
+/// Check that a polygon is simple: its boundary has distinct vertices and
+/// encloses some area without touching or crossing itself.
+pub fn validate(polygon: &[Point]) -> Result<(), InvalidPolygon> {
+    if polygon.len() < 3 {
+        return Err(InvalidPolygon::TooFewVertices);
+    }
+    if !polygon.iter().all(is_finite) {
+        return Err(InvalidPolygon::NonFinite);
+    }
+    let mut sorted = polygon.to_vec();
+    sorted.sort();
+    if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(InvalidPolygon::DuplicateVertex);
+    }
+    // a boundary may cross itself so as to enclose no net area
+    if crossing(polygon).is_some() {
+        return Err(InvalidPolygon::SelfIntersecting);
+    }
+    // while one lying along a line always doubles back on itself
+    if signed_area(polygon) == 0.0 {
+        return Err(InvalidPolygon::ZeroArea);
+    }
+    if has_fold(polygon) {
+        return Err(InvalidPolygon::SelfIntersecting);
+    }
+    Ok(())
+}
+
+/// The direction in which the boundary winds, or `Collinear` if it encloses
+/// no net area.
+pub fn winding(polygon: &[Point]) -> Orientation {
+    let area = signed_area(polygon);
+    if 0.0 < area {
+        Orientation::CounterClockwise
+    } else if area < 0.0 {
+        Orientation::Clockwise
+    } else {
+        Orientation::Collinear
+    }
+}
+
+/// Turn any polygon into simple polygons covering the same region, each
+/// counterclockwise in a y-up frame. Repeated and collinear vertices are
+/// removed, the boundary is cut into separate loops wherever it meets
+/// itself, and loops without area are dropped.
+pub fn repair(polygon: &[Point]) -> Vec<Vec<Point>> {
+    let mut pending = vec![polygon.iter().filter(|p| is_finite(p)).cloned().collect()];
+    let mut repaired = Vec::new();
+    while let Some(polygon) = pending.pop() {
+        let mut polygon = remove_collinear(dedup_cycle(polygon));
+        if polygon.len() < 3 {
+            continue;
+        }
+        match crossing(&polygon) {
+            Some((i, j, point)) => {
+                let mut inner = vec![point.clone()];
+                inner.extend_from_slice(&polygon[i + 1..=j]);
+                let mut outer = vec![point];
+                outer.extend_from_slice(&polygon[j + 1..]);
+                outer.extend_from_slice(&polygon[..=i]);
+                pending.push(inner);
+                pending.push(outer);
+            }
+            None => {
+                if winding(&polygon) == Orientation::Clockwise {
+                    polygon.reverse();
+                }
+                repaired.push(polygon);
+            }
+        }
+    }
+    repaired
+}
+
+/// The first pair of non-adjacent edges `i` and `j` that meet, with a point
+/// they share.
+fn crossing(polygon: &[Point]) -> Option<(usize, usize, Point)> {
+    let n = polygon.len();
+    let edge = |i: usize| (&polygon[i], &polygon[(i + 1) % n]);
+    (0..n)
+        .flat_map(|i| (i + 2..n).map(move |j| (i, j)))
+        .filter(|&(i, j)| !(i == 0 && j == n - 1))
+        .find_map(|(i, j)| {
+            let ((a, b), (c, d)) = (edge(i), edge(j));
+            if segments_meet(a, b, c, d) {
+                Some((i, j, meeting_point(a, b, c, d)))
+            } else {
+                None
+            }
+        })
+}
+
+/// A point shared by segments `ab` and `cd`, which are known to meet. An
+/// endpoint on the other segment is preferred, being exact where the
+/// crossing of the lines may be rounded.
+fn meeting_point(a: &Point, b: &Point, c: &Point, d: &Point) -> Point {
+    let on = |p: &Point, (s, t): (&Point, &Point)| {
+        orient2d(s, t, p) == 0.0 && p.sub(s).dot(&p.sub(t)) <= 0.0
+    };
+    ([c, d].iter().copied().find(|p| on(p, (a, b))))
+        .or_else(|| [a, b].iter().copied().find(|p| on(p, (c, d))))
+        .cloned()
+        .or_else(|| intersect(Inter::LineLine, a, b, c, d))
+        .unwrap_or_else(|| a.clone())
+}
+
+/// Whether the boundary doubles back on itself along a line.
+fn has_fold(polygon: &[Point]) -> bool {
+    let n = polygon.len();
+    (0..n).any(|i| {
+        let (a, b, c) = (&polygon[i], &polygon[(i + 1) % n], &polygon[(i + 2) % n]);
+        orient2d(a, b, c) == 0.0 && b.sub(a).dot(&c.sub(b)) < 0.0
+    })
+}
+
+fn remove_collinear(mut polygon: Vec<Point>) -> Vec<Point> {
+    let mut i = 0;
+    while 3 <= polygon.len() && i < polygon.len() {
+        let n = polygon.len();
+        if orient2d(
+            &polygon[(i + n - 1) % n],
+            &polygon[i],
+            &polygon[(i + 1) % n],
+        ) == 0.0
+        {
+            polygon.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod test_validity {
+    use super::*;
+    use crate::geometry::{square, Polygon};
+
+    fn bowtie() -> Vec<Point> {
+        vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 0.0),
+            Point::new(0.0, 10.0),
+        ]
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut square = square(0.0, 0.0, 10.0);
+        assert_eq!(validate(&square), Ok(()));
+        assert_eq!(winding(&square), Orientation::CounterClockwise);
+        square.reverse();
+        assert_eq!(winding(&square), Orientation::Clockwise);
+        assert_eq!(validate(&square[..2]), Err(InvalidPolygon::TooFewVertices));
+        let flat = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+        ];
+        assert_eq!(validate(&flat), Err(InvalidPolygon::ZeroArea));
+        let mut repeated = square.clone();
+        repeated.push(square[0].clone());
+        assert_eq!(validate(&repeated), Err(InvalidPolygon::DuplicateVertex));
+        assert_eq!(validate(&bowtie()), Err(InvalidPolygon::SelfIntersecting));
+        let mut lopsided = bowtie();
+        lopsided[3] = Point::new(0.0, 5.0);
+        assert_eq!(validate(&lopsided), Err(InvalidPolygon::SelfIntersecting));
+        let mut spike = square.clone();
+        spike.insert(1, Point::new(-5.0, 10.0));
+        assert_eq!(validate(&spike), Err(InvalidPolygon::SelfIntersecting));
+    }
+
+    #[test]
+    fn test_repair() {
+        let pieces = repair(&bowtie());
+        assert_eq!(pieces.len(), 2);
+        for piece in pieces.iter() {
+            assert_eq!(validate(piece), Ok(()));
+            assert_eq!(winding(piece), Orientation::CounterClockwise);
+            assert_eq!(Polygon(piece).area(), 25.0);
+        }
+        // repeated and collinear vertices
+        let mut square = square(0.0, 0.0, 10.0);
+        square.reverse();
+        square.push(Point::new(0.0, 5.0));
+        square.push(Point::new(0.0, 5.0));
+        let pieces = repair(&square);
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].len(), 4);
+        assert_eq!(validate(&pieces[0]), Ok(()));
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+/// A bounded record of values by tick, in increasing order of tick, which
+/// discards the oldest values once full.
+#[derive(Clone)]
+pub struct History<T> {
+    capacity: usize,
+    entries: VecDeque<(u32, T)>,
+}
+
+impl<T> History<T> {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            capacity: capacity.max(1),
+            entries: VecDeque::with_capacity(capacity.max(1)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record `value` at `tick`, replacing any values at or after `tick`.
+    pub fn push(&mut self, tick: u32, value: T) {
+        self.truncate(tick);
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((tick, value));
+    }
+
+    /// Remove values at or after `tick`.
+    pub fn truncate(&mut self, tick: u32) {
+        while self.entries.back().is_some_and(|(t, _)| tick <= *t) {
+            self.entries.pop_back();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Remove and return the most recent value.
+    pub fn pop(&mut self) -> Option<(u32, T)> {
+        self.entries.pop_back()
+    }
+
+    pub fn get(&self, tick: u32) -> Option<&T> {
+        let first = self.entries.front()?.0;
+        let index = tick.checked_sub(first)? as usize;
+        match self.entries.get(index) {
+            Some((t, value)) if *t == tick => Some(value),
+            _ => (self.entries.iter()).find_map(|(t, value)| (*t == tick).then_some(value)),
+        }
+    }
+
+    pub fn oldest(&self) -> Option<u32> {
+        self.entries.front().map(|(tick, _)| *tick)
+    }
+
+    pub fn latest(&self) -> Option<u32> {
+        self.entries.back().map(|(tick, _)| *tick)
+    }
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        let mut history = History::new(3);
+        for tick in 0..5 {
+            history.push(tick, tick * 10);
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.oldest(), Some(2));
+        assert_eq!(history.get(1), None);
+        assert_eq!(history.get(4), Some(&40));
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut history = History::new(8);
+        for tick in 0..5 {
+            history.push(tick, tick);
+        }
+        history.push(2, 20);
+        assert_eq!(history.latest(), Some(2));
+        assert_eq!(history.get(2), Some(&20));
+        assert_eq!(history.get(3), None);
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut history = History::new(8);
+        history.push(1, 'a');
+        history.push(5, 'b');
+        assert_eq!(history.get(5), Some(&'b'));
+        assert_eq!(history.get(2), None);
+        assert_eq!(history.pop(), Some((5, 'b')));
+        assert_eq!(history.get(5), None);
+    }
+}
@@ -1,22 +1,37 @@
 use rand::SeedableRng;
 use rand_pcg::Pcg32;
+use std::hash::Hasher;
+use std::mem;
 
 use crate::asteroid;
 use crate::asteroid::Asteroid;
 
 use crate::blast::Blast;
-use crate::geometry::Size;
+use crate::event::GameEvent;
+use crate::geometry::{Point, Size};
 use crate::motion::Collide;
 use crate::particle::Particle;
 use crate::player;
 use crate::player::Player;
+use crate::rhythm::Rhythm;
+use crate::util::{Fnv, Timer};
 use crate::Controls;
 
 mod stats {
+    use crate::util::Timer;
+
+    const COMBO_WINDOW: f64 = 1.5; // seconds
+    const COMBO_DECAY: f64 = 0.75; // seconds per multiplier step
+    const MAX_MULTIPLIER: u32 = 8;
+
+    #[derive(Clone)]
     pub struct Stats {
         fired: u32,
         hit: u32,
         cleared: f64,
+        weighted: f64, // cleared mass, scaled by multiplier at time of hit
+        multiplier: u32,
+        combo: Timer,
         score: u32, // cached
     }
 
@@ -26,9 +41,21 @@ mod stats {
                 fired: 0,
                 hit: 0,
                 cleared: 0.0,
+                weighted: 0.0,
+                multiplier: 1,
+                combo: Timer::new(0.0),
                 score: 0,
             }
         }
+        pub fn step(&mut self, dt: f64) {
+            self.combo.step(dt);
+            if self.combo.is_elapsed() && 1 < self.multiplier {
+                self.multiplier -= 1;
+                if 1 < self.multiplier {
+                    self.combo = Timer::new(COMBO_DECAY);
+                }
+            }
+        }
         pub fn increment_fired(&mut self) {
             self.fired += 1;
             self.refresh_score();
@@ -37,31 +64,223 @@ mod stats {
             self.hit += 1;
             self.refresh_score();
         }
+        /// Extend the combo chain by a hit that destroyed `fragments` pieces.
+        /// Returns the new multiplier.
+        pub fn extend_combo(&mut self, fragments: u32) -> u32 {
+            if !self.combo.is_elapsed() {
+                self.multiplier += 1;
+            }
+            self.multiplier += fragments.saturating_sub(1);
+            self.multiplier = self.multiplier.min(MAX_MULTIPLIER);
+            self.combo = Timer::new(COMBO_WINDOW);
+            self.multiplier
+        }
         pub fn add_cleared(&mut self, mass: f64) {
             self.cleared += mass;
+            self.weighted += mass * self.multiplier as f64;
             self.refresh_score();
         }
         fn refresh_score(&mut self) {
             let efficiency = (self.cleared / self.fired as f64) / 400.0;
             let accuracy = self.hit as f64 / self.fired as f64;
-            self.score = (self.cleared * efficiency.sqrt() * accuracy).round() as u32;
+            self.score = (self.weighted * efficiency.sqrt() * accuracy).round() as u32;
         }
         pub fn score(&self) -> u32 {
             self.score
         }
+        pub fn multiplier(&self) -> u32 {
+            self.multiplier
+        }
+    }
+
+    #[cfg(test)]
+    mod test_stats {
+        use super::*;
+
+        #[test]
+        fn test_single_hit() {
+            let mut stats = Stats::new();
+            assert_eq!(stats.extend_combo(0), 1);
+            assert_eq!(stats.extend_combo(1), 1 + 1);
+        }
+
+        #[test]
+        fn test_window_elapsed() {
+            let mut stats = Stats::new();
+            stats.extend_combo(1);
+            stats.step(COMBO_WINDOW);
+            assert_eq!(stats.extend_combo(1), 1);
+        }
+
+        #[test]
+        fn test_fragments() {
+            let mut stats = Stats::new();
+            assert_eq!(stats.extend_combo(3), 3);
+            assert_eq!(stats.extend_combo(20), MAX_MULTIPLIER);
+        }
+
+        #[test]
+        fn test_decay() {
+            let mut stats = Stats::new();
+            stats.extend_combo(4);
+            stats.step(COMBO_WINDOW);
+            assert_eq!(stats.multiplier(), 3);
+            stats.step(COMBO_DECAY);
+            assert_eq!(stats.multiplier(), 2);
+            stats.step(COMBO_DECAY);
+            assert_eq!(stats.multiplier(), 1);
+            stats.step(COMBO_DECAY);
+            assert_eq!(stats.multiplier(), 1);
+        }
+
+        #[test]
+        fn test_weighted_score() {
+            let mut plain = Stats::new();
+            let mut chained = Stats::new();
+            for stats in [&mut plain, &mut chained].iter_mut() {
+                stats.increment_fired();
+                stats.increment_fired();
+                stats.increment_hit();
+                stats.increment_hit();
+            }
+            plain.add_cleared(400.0);
+            plain.add_cleared(400.0);
+            chained.extend_combo(1);
+            chained.add_cleared(400.0);
+            chained.extend_combo(1);
+            chained.add_cleared(400.0);
+            assert_eq!(plain.score(), 800);
+            assert_eq!(chained.score(), 1200);
+        }
     }
 }
 
 use stats::Stats;
 
+const SPAWN_SPACING: f64 = 80.0; // px
+
+const POPUP_DURATION: f64 = 1.2; // seconds
+const POPUP_SPEED: f64 = 40.0; // px / second
+
+/// Floating multiplier text, shown where a combo was extended.
+#[derive(Clone)]
+pub struct Popup {
+    position: Point,
+    multiplier: u32,
+    timer: Timer,
+}
+
+impl Popup {
+    fn new(position: Point, multiplier: u32) -> Self {
+        Popup {
+            position,
+            multiplier,
+            timer: Timer::new(POPUP_DURATION),
+        }
+    }
+
+    fn step(&mut self, dt: f64) {
+        self.position.y -= POPUP_SPEED * dt;
+        self.timer.step(dt);
+    }
+
+    fn is_expired(&self) -> bool {
+        self.timer.is_elapsed()
+    }
+
+    pub fn position(&self) -> &Point {
+        &self.position
+    }
+
+    pub fn text(&self) -> String {
+        format!("{}X", self.multiplier)
+    }
+
+    pub fn alpha(&self) -> f64 {
+        (self.timer.remaining() / POPUP_DURATION).max(0.0)
+    }
+}
+
+/// Settings shared by all levels of a game.
+#[derive(Clone)]
+pub struct Rules {
+    pub players: usize,
+    pub friendly_fire: bool,
+    pub versus: Option<Versus>,
+}
+
+impl Rules {
+    pub fn single() -> Self {
+        Rules {
+            players: 1,
+            friendly_fire: false,
+            versus: None,
+        }
+    }
+
+    pub fn coop(players: usize, friendly_fire: bool) -> Self {
+        Rules {
+            players: players.max(1),
+            friendly_fire,
+            versus: None,
+        }
+    }
+
+    pub fn versus(players: usize, versus: Versus) -> Self {
+        Rules {
+            players: players.max(2),
+            friendly_fire: true,
+            versus: Some(versus),
+        }
+    }
+}
+
+/// Settings for a competitive round, where players score frags by destroying
+/// each other, and the asteroid field is replenished whenever it is cleared.
+#[derive(Clone)]
+pub struct Versus {
+    pub frag_limit: u32,
+    pub respawn_delay: f64, // seconds
+    pub bounded: bool,
+}
+
+impl Versus {
+    pub fn standard() -> Self {
+        Versus {
+            frag_limit: 5,
+            respawn_delay: 3.0,
+            bounded: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Pilot {
+    player: Option<Player>,
+    stats: Stats,
+    frags: u32,
+    respawn: Option<Timer>,
+}
+
+impl Pilot {
+    fn destroy(&mut self, rules: &Rules) {
+        self.player = None;
+        self.respawn = (rules.versus.as_ref()).map(|versus| Timer::new(versus.respawn_delay));
+    }
+}
+
+#[derive(Clone)]
 pub struct Level {
     rng: Pcg32,
     number: u8,
-    stats: Stats,
-    player: Option<Player>,
+    rules: Rules,
+    pilots: Vec<Pilot>,
     asteroids: Vec<Asteroid>,
     blasts: Vec<Blast>,
     particles: Vec<Particle>,
+    popups: Vec<Popup>,
+    rhythm: Rhythm,
+    events: Vec<GameEvent>,
 }
 
 impl Level {
@@ -69,15 +288,41 @@ impl Level {
         Pcg32::seed_from_u64(1979 * 11 * number as u64)
     }
 
-    pub fn new(number: u8, bounds: &Size) -> Self {
+    pub fn new(number: u8, bounds: &Size, rules: &Rules) -> Self {
+        let asteroids = Level::asteroid_field(number, bounds);
         Level {
             rng: Level::rng(number),
-            number: number,
-            stats: Stats::new(),
-            player: Some(Player::new(bounds.center())),
-            asteroids: Level::asteroid_field(number, bounds),
+            number,
+            rules: rules.clone(),
+            pilots: (0..rules.players)
+                .map(|i| Pilot {
+                    player: Some(Level::spawn_player(i, rules, bounds)),
+                    stats: Stats::new(),
+                    frags: 0,
+                    respawn: None,
+                })
+                .collect(),
+            rhythm: Rhythm::new(asteroids.iter().map(|a| a.mass()).sum()),
+            asteroids,
             blasts: Vec::new(),
             particles: Vec::new(),
+            popups: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Players are spaced in a row across the center of the field.
+    fn spawn_point(i: usize, count: usize, bounds: &Size) -> Point {
+        let center = bounds.center();
+        let offset = i as f64 - 0.5 * (count - 1) as f64;
+        Point::new(center.x + offset * SPAWN_SPACING, center.y)
+    }
+
+    fn spawn_player(i: usize, rules: &Rules, bounds: &Size) -> Player {
+        let player = Player::new(Level::spawn_point(i, rules.players, bounds));
+        match &rules.versus {
+            Some(versus) if versus.bounded => player.confined(),
+            _ => player,
         }
     }
 
@@ -89,11 +334,34 @@ impl Level {
     pub fn number(&self) -> u8 {
         self.number
     }
-    pub fn score(&self) -> u32 {
-        self.stats.score()
+    pub fn scores(&self) -> Vec<u32> {
+        self.pilots
+            .iter()
+            .map(|pilot| pilot.stats.score())
+            .collect()
+    }
+    pub fn multipliers(&self) -> Vec<u32> {
+        (self.pilots.iter())
+            .map(|pilot| pilot.stats.multiplier())
+            .collect()
+    }
+    pub fn frags(&self) -> Vec<u32> {
+        self.pilots.iter().map(|pilot| pilot.frags).collect()
     }
-    pub fn player(&self) -> &Option<Player> {
-        &self.player
+    /// The first player to reach the frag limit, in versus rules.
+    pub fn winner(&self) -> Option<usize> {
+        let limit = self.rules.versus.as_ref()?.frag_limit;
+        (self.pilots.iter()).position(|pilot| limit <= pilot.frags)
+    }
+    pub fn player(&self, i: usize) -> Option<&Player> {
+        self.pilots.get(i).and_then(|pilot| pilot.player.as_ref())
+    }
+    pub fn players(&self) -> impl Iterator<Item = &Player> {
+        self.pilots.iter().filter_map(|pilot| pilot.player.as_ref())
+    }
+    /// Whether every player has been destroyed, with none waiting to respawn.
+    pub fn is_destroyed(&self) -> bool {
+        (self.pilots.iter()).all(|pilot| pilot.player.is_none() && pilot.respawn.is_none())
     }
     pub fn asteroids(&self) -> &Vec<Asteroid> {
         &self.asteroids
@@ -104,36 +372,125 @@ impl Level {
     pub fn particles(&self) -> &Vec<Particle> {
         &self.particles
     }
+    pub fn popups(&self) -> &Vec<Popup> {
+        &self.popups
+    }
+    pub fn beat_phase(&self) -> f64 {
+        self.rhythm.phase()
+    }
+
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        mem::take(&mut self.events)
+    }
 
-    pub fn step(&mut self, dt: f64, bounds: &Size, controls: Controls) -> () {
-        if dt <= 0.0 {
-            return ();
+    /// A hash of the simulation state, for detecting when copies of a level
+    /// have diverged, such as between peers of a networked game.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Fnv::new();
+        hasher.write_u8(self.number);
+        for pilot in self.pilots.iter() {
+            hasher.write_u32(pilot.stats.score());
+            hasher.write_u32(pilot.frags);
+            match &pilot.player {
+                Some(player) => write_points(&mut hasher, &player.hull()),
+                None => hasher.write_usize(0),
+            }
+        }
+        for asteroid in self.asteroids.iter() {
+            write_points(&mut hasher, &asteroid.to_path());
+        }
+        for blast in self.blasts.iter() {
+            let (a, b) = blast.endpoints();
+            write_points(&mut hasher, &[a, b]);
+        }
+        for particle in self.particles.iter() {
+            let (a, b) = particle.endpoints();
+            write_points(&mut hasher, &[a, b]);
+        }
+        hasher.finish()
+    }
+
+    /// Step the level, with controls given for each player in order.
+    /// Players without controls are given none.
+    pub fn step(&mut self, dt: f64, bounds: &Size, controls: &[Controls]) {
+        self.step_split(dt, dt, bounds, controls);
+    }
+
+    /// Step with separate time deltas for the players, which includes their
+    /// blasts, and for the rest of the world.
+    pub fn step_split(
+        &mut self,
+        player_dt: f64,
+        world_dt: f64,
+        bounds: &Size,
+        controls: &[Controls],
+    ) {
+        let player_dt = player_dt.max(0.0);
+        let world_dt = world_dt.max(0.0);
+        if player_dt == 0.0 && world_dt == 0.0 {
+            return;
         }
 
         // step
 
-        if let Some(player) = &mut self.player {
-            player.step(dt, bounds, controls);
-            if let Some(blast) = player.fire_blast() {
-                self.stats.increment_fired();
-                self.blasts.push(blast);
+        for (i, pilot) in self.pilots.iter_mut().enumerate() {
+            pilot.stats.step(player_dt);
+            if let Some(timer) = &mut pilot.respawn {
+                timer.step(player_dt);
+                if timer.is_elapsed() {
+                    pilot.player = Some(Level::spawn_player(i, &self.rules, bounds));
+                    pilot.respawn = None;
+                }
+            }
+            if let Some(player) = &mut pilot.player {
+                let controls = controls.get(i).cloned().unwrap_or_else(|| Controls::new(0));
+                player.step(player_dt, bounds, controls);
+                if let Some(blast) = player.fire_blast() {
+                    pilot.stats.increment_fired();
+                    self.events.push(GameEvent::BlastFired {
+                        player: i,
+                        position: blast.endpoints().0,
+                        speed: blast.velocity().length(),
+                    });
+                    self.blasts.push(blast.with_owner(i));
+                }
             }
         }
 
         for asteroid in self.asteroids.iter_mut() {
-            asteroid.step(dt, bounds);
+            asteroid.step(world_dt, bounds);
         }
 
         for blast in self.blasts.iter_mut() {
+            let dt = if blast.owner().is_some() {
+                player_dt
+            } else {
+                world_dt
+            };
             blast.step(dt, bounds);
         }
         self.blasts.retain(|blast| !blast.is_expired());
 
         for particle in self.particles.iter_mut() {
-            particle.step(dt, bounds);
+            particle.step(world_dt, bounds);
         }
         self.particles.retain(|particle| !particle.is_expired());
 
+        for popup in self.popups.iter_mut() {
+            popup.step(player_dt);
+        }
+        self.popups.retain(|popup| !popup.is_expired());
+
+        if !self.is_destroyed() && !self.asteroids.is_empty() {
+            let remaining_mass = self.asteroids.iter().map(|a| a.mass()).sum();
+            if let Some(beat) = self.rhythm.step(world_dt, remaining_mass) {
+                self.events.push(GameEvent::Beat {
+                    high: beat.high,
+                    period: beat.period,
+                });
+            }
+        }
+
         // interact: asteroids * blasts
 
         let mut asteroids = Vec::new();
@@ -141,11 +498,27 @@ impl Level {
             if let Some((i, mut impact)) =
                 interact_asteroid_blasts(&mut self.rng, &asteroid, &self.blasts)
             {
+                let owner = self.blasts.remove(i).owner();
                 let remaining_mass = impact.fragments.iter().map(|f| f.mass()).sum::<f64>();
-                self.stats.increment_hit();
-                self.stats.add_cleared(asteroid.mass() - remaining_mass);
+                self.events.push(GameEvent::AsteroidSplit {
+                    player: owner,
+                    position: impact.point.clone(),
+                    speed: impact.speed,
+                    mass: asteroid.mass(),
+                    cleared: asteroid.mass() - remaining_mass,
+                    fragments: impact.fragments.len() as u32,
+                });
+                let pilots = &mut self.pilots;
+                if let Some(pilot) = owner.and_then(|i| pilots.get_mut(i)) {
+                    let stats = &mut pilot.stats;
+                    stats.increment_hit();
+                    let multiplier = stats.extend_combo(impact.destroyed);
+                    if 1 < multiplier {
+                        self.popups.push(Popup::new(impact.point, multiplier));
+                    }
+                    stats.add_cleared(asteroid.mass() - remaining_mass);
+                }
                 //
-                self.blasts.remove(i);
                 asteroids.append(&mut impact.fragments);
                 self.particles.append(&mut impact.particles);
             } else {
@@ -154,32 +527,55 @@ impl Level {
         }
         self.asteroids = asteroids;
 
-        // interact: player * blasts
+        let mut frags = Vec::new();
+        for (i, pilot) in self.pilots.iter_mut().enumerate() {
+            // interact: player * blasts
 
-        if let Some(player) = &mut self.player {
-            if let Some((i, mut impact)) =
-                interact_player_blasts(&mut self.rng, player, &self.blasts)
-            {
-                self.blasts.remove(i);
-                self.particles.append(&mut impact.particles);
-                if impact.destroyed {
-                    self.player = None;
+            if let Some(player) = &mut pilot.player {
+                let friendly_fire = self.rules.friendly_fire;
+                if let Some((j, mut impact)) =
+                    interact_player_blasts(&mut self.rng, player, &self.blasts, |blast| {
+                        friendly_fire || blast.owner().filter(|&owner| owner != i).is_none()
+                    })
+                {
+                    let owner = self.blasts.remove(j).owner();
+                    self.particles.append(&mut impact.particles);
+                    push_player_events(&mut self.events, i, player, &impact);
+                    if impact.destroyed {
+                        pilot.destroy(&self.rules);
+                        if let Some(owner) = owner.filter(|&owner| owner != i) {
+                            frags.push((owner, i));
+                        }
+                    }
                 }
             }
-        }
 
-        // interact: player * asteroids
+            // interact: player * asteroids
 
-        if let Some(player) = &mut self.player {
-            if let Some(mut impact) =
-                interact_player_asteroids(&mut self.rng, player, &mut self.asteroids)
-            {
-                self.particles.append(&mut impact.particles);
-                if impact.destroyed {
-                    self.player = None;
+            if let Some(player) = &mut pilot.player {
+                if let Some(mut impact) =
+                    interact_player_asteroids(&mut self.rng, player, &mut self.asteroids)
+                {
+                    self.particles.append(&mut impact.particles);
+                    push_player_events(&mut self.events, i, player, &impact);
+                    if impact.destroyed {
+                        pilot.destroy(&self.rules);
+                    }
                 }
             }
         }
+
+        if self.rules.versus.is_some() {
+            for (player, victim) in frags {
+                self.pilots[player].frags += 1;
+                self.events
+                    .push(GameEvent::PlayerFragged { player, victim });
+            }
+            if self.asteroids.is_empty() {
+                let count = 3 + 2 * self.number as u32;
+                self.asteroids = Asteroid::field(&mut self.rng, bounds, count, 100.0);
+            }
+        }
     }
 }
 
@@ -195,12 +591,17 @@ fn interact_asteroid_blasts(
     })
 }
 
-fn interact_player_blasts(
+fn interact_player_blasts<F>(
     rng: &mut Pcg32,
     player: &mut Player,
     blasts: &Vec<Blast>,
-) -> Option<(usize, player::Impact)> {
+    is_harmful: F,
+) -> Option<(usize, player::Impact)>
+where
+    F: Fn(&Blast) -> bool,
+{
     (blasts.iter().enumerate())
+        .filter(|(_, blast)| is_harmful(blast))
         .find_map(|(i, blast)| player.interact_blast(rng, blast).map(|impact| (i, impact)))
 }
 
@@ -211,3 +612,225 @@ fn interact_player_asteroids(
 ) -> Option<player::Impact> {
     (asteroids.iter_mut()).find_map(|asteroid| player.interact_asteroid(rng, asteroid))
 }
+
+fn write_points<H: Hasher>(hasher: &mut H, points: &[Point]) {
+    hasher.write_usize(points.len());
+    for point in points {
+        hasher.write_u64(point.x.to_bits());
+        hasher.write_u64(point.y.to_bits());
+    }
+}
+
+fn push_player_events(
+    events: &mut Vec<GameEvent>,
+    i: usize,
+    player: &Player,
+    impact: &player::Impact,
+) {
+    events.push(GameEvent::PlayerHit {
+        player: i,
+        position: impact.point.clone(),
+        speed: impact.speed,
+    });
+    events.push(if impact.destroyed {
+        GameEvent::PlayerDestroyed {
+            player: i,
+            position: player.center().clone(),
+            speed: impact.speed,
+        }
+    } else {
+        GameEvent::ShieldBounce {
+            player: i,
+            position: impact.point.clone(),
+            speed: impact.speed,
+        }
+    });
+}
+
+#[cfg(test)]
+mod test_level {
+    use super::*;
+    use crate::motion::Movement;
+    use crate::{FIRE, LEFT};
+    use std::f64::consts::FRAC_PI_2;
+
+    fn bounds() -> Size {
+        Size {
+            width: 1200.0,
+            height: 900.0,
+        }
+    }
+
+    #[test]
+    fn test_coop_destroyed() {
+        let rules = Rules::coop(2, false);
+        let mut level = Level::new(1, &bounds(), &rules);
+        level.pilots[0].destroy(&rules);
+        assert!(!level.is_destroyed());
+        level.pilots[1].destroy(&rules);
+        assert!(level.is_destroyed());
+        assert_eq!(level.winner(), None);
+    }
+
+    #[test]
+    fn test_coop_controls() {
+        let mut level = Level::new(1, &bounds(), &Rules::coop(2, false));
+        level.asteroids.clear();
+        let controls = [Controls::new(0), Controls::new(LEFT | FIRE)];
+        level.step(1.0 / 60.0, &bounds(), &controls);
+        let events = level.drain_events();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], GameEvent::BlastFired { player: 1, .. }));
+        assert_eq!(level.blasts().len(), 1);
+        assert_eq!(level.blasts()[0].owner(), Some(1));
+        assert_eq!(level.player(0).unwrap().placement().rotation, -FRAC_PI_2);
+        assert!(level.player(1).unwrap().placement().rotation < -FRAC_PI_2);
+    }
+
+    /// Whether the first player survives a blast fired by the second.
+    fn survives_teammate_blast(friendly_fire: bool) -> bool {
+        let mut level = Level::new(1, &bounds(), &Rules::coop(2, friendly_fire));
+        level.asteroids.clear();
+        let below = level
+            .player(0)
+            .unwrap()
+            .center()
+            .add(&Point::new(0.0, 40.0));
+        level.blasts = vec![Blast::new(below, 800.0, -FRAC_PI_2).with_owner(1)];
+        step_until(&mut level, |level| level.blasts().is_empty());
+        level.player(0).is_some()
+    }
+
+    #[test]
+    fn test_coop_friendly_fire() {
+        assert!(survives_teammate_blast(false));
+        assert!(!survives_teammate_blast(true));
+    }
+
+    #[test]
+    fn test_versus_respawn() {
+        let versus = Versus::standard();
+        let delay = versus.respawn_delay;
+        let rules = Rules::versus(2, versus);
+        let mut level = Level::new(1, &bounds(), &rules);
+        level.pilots[0].destroy(&rules);
+        assert!(level.player(0).is_none());
+        assert!(!level.is_destroyed());
+        level.step(delay, &bounds(), &[]);
+        assert!(level.player(0).is_some());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let mut a = Level::new(2, &bounds(), &Rules::single());
+        let mut b = Level::new(2, &bounds(), &Rules::single());
+        let controls = [Controls::new(4 | 8)];
+        for _ in 0..120 {
+            a.step(1.0 / 60.0, &bounds(), &controls);
+            b.step(1.0 / 60.0, &bounds(), &controls);
+            assert_eq!(a.checksum(), b.checksum());
+        }
+        b.step(1.0 / 60.0, &bounds(), &controls);
+        assert_ne!(a.checksum(), b.checksum());
+    }
+
+    #[test]
+    fn test_step_split() {
+        let mut level = Level::new(1, &bounds(), &Rules::single());
+        let before = level.asteroids()[0].placement().position.clone();
+        level.step_split(0.1, 0.0, &bounds(), &[Controls::new(8)]);
+        assert_eq!(level.asteroids()[0].placement().position, before);
+        assert!(!level.blasts().is_empty());
+        let blast = level.blasts()[0].endpoints().0;
+        level.step_split(0.05, 0.0, &bounds(), &[]);
+        assert_ne!(level.blasts()[0].endpoints().0, blast);
+    }
+
+    fn square(center: Point, size: f64) -> Asteroid {
+        let half = size / 2.0;
+        Asteroid::from_polygon(&vec![
+            Point::new(center.x - half, center.y - half),
+            Point::new(center.x + half, center.y - half),
+            Point::new(center.x + half, center.y + half),
+            Point::new(center.x - half, center.y + half),
+        ])
+    }
+
+    /// Step without controls until `done`, returning the events drained.
+    fn step_until<F>(level: &mut Level, done: F) -> Vec<GameEvent>
+    where
+        F: Fn(&Level) -> bool,
+    {
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            level.step(1.0 / 60.0, &bounds(), &[]);
+            events.append(&mut level.drain_events());
+            if done(level) {
+                break;
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn test_fire_and_clear_events() {
+        let mut level = Level::new(1, &bounds(), &Rules::single());
+        // a single asteroid small enough to be destroyed, in the line of fire
+        let target = square(Point::new(600.0, 300.0), 16.0);
+        let mass = target.mass();
+        level.asteroids = vec![target];
+
+        level.step(1.0 / 60.0, &bounds(), &[Controls::new(FIRE)]);
+        let events = level.drain_events();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], GameEvent::BlastFired { player: 0, .. }));
+        assert!(level.drain_events().is_empty());
+
+        let events = step_until(&mut level, |level| level.asteroids().is_empty());
+        assert!(level.asteroids().is_empty());
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            GameEvent::AsteroidSplit {
+                player,
+                cleared,
+                fragments,
+                ..
+            } => {
+                assert_eq!(*player, Some(0));
+                assert_eq!(*cleared, mass);
+                assert_eq!(*fragments, 0);
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert!(level.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_destroyed_events() {
+        let mut level = Level::new(1, &bounds(), &Rules::single());
+        // an asteroid falling onto the player from above
+        let mut asteroid = square(Point::new(600.0, 400.0), 40.0);
+        asteroid.set_movement(Movement {
+            velocity: Point::new(0.0, 300.0),
+            angular_velocity: 0.0,
+        });
+        level.asteroids = vec![asteroid];
+
+        let events = step_until(&mut level, |level| level.is_destroyed());
+        assert!(level.is_destroyed());
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], GameEvent::PlayerHit { player: 0, .. }));
+        assert!(matches!(
+            events[1],
+            GameEvent::PlayerDestroyed { player: 0, .. }
+        ));
+        assert!(level.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_versus_winner() {
+        let mut level = Level::new(1, &bounds(), &Rules::versus(3, Versus::standard()));
+        level.pilots[2].frags = Versus::standard().frag_limit;
+        assert_eq!(level.winner(), Some(2));
+    }
+}
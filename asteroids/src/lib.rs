@@ -1,57 +1,196 @@
 use rand::SeedableRng;
 use rand_pcg::Pcg32;
+use std::hash::Hasher;
+use std::mem;
 
 mod asteroid;
+pub mod audio;
 mod blast;
+mod event;
 pub mod geometry;
+pub mod history;
 pub mod iter;
 mod level;
+mod menu;
 pub mod motion;
 mod particle;
 mod player;
+mod rewind;
+mod rhythm;
+mod time;
 pub mod typography;
 mod util;
 
 pub use asteroid::Asteroid;
 pub use blast::Blast;
-use geometry::{Point, Polyline, Size};
-pub use level::Level;
+pub use event::GameEvent;
+use geometry::{Point, Polyline, Radians, Size};
+pub use level::{Level, Popup, Rules, Versus};
+use menu::Menu;
 pub use particle::{Dispersion, Particle};
 pub use player::Player;
+use rewind::Rewind;
+use time::BulletTime;
+pub use time::{Class, TimeScale};
 use typography::{Align, Font};
-use util::Timer;
+use util::{Fnv, Timer};
 
-pub struct Controls(u32);
+/// Player input, either as a bitmask of digital controls, or as analog axes
+/// along with a bitmask of buttons.
+#[derive(Clone, Copy)]
+pub struct Controls {
+    input: u32,
+    analog: Option<Analog>,
+}
+
+#[derive(Clone, Copy)]
+struct Analog {
+    turn: f64,            // within range [-1, 1], negative turning left
+    thrust: f64,          // within range [0, 1]
+    aim: Option<Radians>, // absolute direction to face
+}
+
+const LEFT: u32 = 1;
+const RIGHT: u32 = 2;
+const THRUST: u32 = 4;
+const FIRE: u32 = 8;
+const SHIELD: u32 = 16;
+const START: u32 = 32;
+const PAUSE: u32 = 64;
+const SLOW: u32 = 128;
+const REWIND: u32 = 256;
+
+const ANALOG_THRESHOLD: f64 = 0.5;
 
 impl Controls {
     pub fn new(input: u32) -> Self {
-        Controls(input)
+        Controls {
+            input,
+            analog: None,
+        }
+    }
+
+    /// Controls from analog axes, where `turn` is within range [-1, 1] and
+    /// `thrust` within [0, 1]. When `aim` is given, the player turns toward it
+    /// instead. The left, right and thrust bits of `buttons` are ignored.
+    pub fn analog(turn: f64, thrust: f64, aim: Option<Radians>, buttons: u32) -> Self {
+        let analog = Analog {
+            turn: if turn.is_nan() {
+                0.0
+            } else {
+                turn.clamp(-1.0, 1.0)
+            },
+            thrust: if thrust.is_nan() {
+                0.0
+            } else {
+                thrust.clamp(0.0, 1.0)
+            },
+            aim: aim.filter(|aim| aim.is_finite()),
+        };
+        Controls {
+            input: buttons & !(LEFT | RIGHT | THRUST),
+            analog: Some(analog),
+        }
+    }
+
+    /// The digital controls, with analog axes past a threshold treated as
+    /// pressed.
+    fn bits(&self) -> u32 {
+        match &self.analog {
+            None => self.input,
+            Some(analog) => {
+                self.input
+                    | if analog.turn <= -ANALOG_THRESHOLD {
+                        LEFT
+                    } else {
+                        0
+                    }
+                    | if ANALOG_THRESHOLD <= analog.turn {
+                        RIGHT
+                    } else {
+                        0
+                    }
+                    | if 0.0 < analog.thrust { THRUST } else { 0 }
+            }
+        }
     }
 
     pub fn left(&self) -> bool {
-        self.0 & 1 != 0
+        self.bits() & LEFT != 0
     }
     pub fn right(&self) -> bool {
-        self.0 & 2 != 0
+        self.bits() & RIGHT != 0
     }
     pub fn thrust(&self) -> bool {
-        self.0 & 4 != 0
+        self.bits() & THRUST != 0
     }
     pub fn fire(&self) -> bool {
-        self.0 & 8 != 0
+        self.bits() & FIRE != 0
     }
     pub fn shield(&self) -> bool {
-        self.0 & 16 != 0
+        self.bits() & SHIELD != 0
     }
     pub fn start(&self) -> bool {
-        self.0 & 32 != 0
+        self.bits() & START != 0
+    }
+    pub fn pause(&self) -> bool {
+        self.bits() & PAUSE != 0
+    }
+    pub fn slow(&self) -> bool {
+        self.bits() & SLOW != 0
+    }
+    pub fn rewind(&self) -> bool {
+        self.bits() & REWIND != 0
+    }
+
+    /// Turning, within range [-1, 1].
+    pub fn turn(&self) -> f64 {
+        match &self.analog {
+            Some(analog) => analog.turn,
+            None => match (self.left(), self.right()) {
+                (true, false) => -1.0,
+                (false, true) => 1.0,
+                _ => 0.0,
+            },
+        }
+    }
+    /// Thrust, within range [0, 1].
+    pub fn thrust_amount(&self) -> f64 {
+        match &self.analog {
+            Some(analog) => analog.thrust,
+            None if self.thrust() => 1.0,
+            None => 0.0,
+        }
+    }
+    pub fn aim(&self) -> Option<Radians> {
+        self.analog.and_then(|analog| analog.aim)
+    }
+
+    /// Combine controls, such as from several players.
+    pub fn merge(controls: &[Controls]) -> Controls {
+        Controls::new(
+            controls
+                .iter()
+                .fold(0, |bits, controls| bits | controls.bits()),
+        )
+    }
+
+    /// Controls that are active now but were not active in `previous`.
+    pub fn pressed(&self, previous: &Controls) -> Controls {
+        Controls::new(self.bits() & !previous.bits())
     }
 }
 
 pub struct Game {
     bounds: Size,
     font: FontLibrary,
+    rules: Rules,
     high_score: u32,
+    controls: Controls,
+    time_scale: TimeScale,
+    bullet_time: BulletTime,
+    rewind: Rewind,
+    events: Vec<GameEvent>,
     state: State,
 }
 
@@ -67,14 +206,14 @@ enum State {
         asteroids: Vec<Asteroid>,
     },
     LevelIntro {
-        score: u32,
+        scores: Vec<u32>,
         number: u8,
         text: Vec<Polyline>,
         asteroids: Vec<Asteroid>,
         timer: Timer,
     },
     ActiveLevel {
-        score: u32,
+        scores: Vec<u32>,
         level: Level,
         state: LevelState,
     },
@@ -82,8 +221,29 @@ enum State {
 
 enum LevelState {
     Playing,
-    Cleared { text: Vec<Polyline>, timer: Timer },
-    Destroyed { text: Vec<Polyline>, timer: Timer },
+    Cleared {
+        text: Vec<Polyline>,
+        timer: Timer,
+    },
+    Destroyed {
+        text: Vec<Polyline>,
+        timer: Timer,
+    },
+    Paused {
+        menu: Menu<Choice>,
+        text: Vec<Polyline>,
+    },
+    Results {
+        text: Vec<Polyline>,
+        timer: Timer,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum Choice {
+    Resume,
+    Restart,
+    Quit,
 }
 
 use LevelState::*;
@@ -91,6 +251,10 @@ use State::*;
 
 impl Game {
     pub fn new() -> Self {
+        Game::with_rules(Rules::single())
+    }
+
+    pub fn with_rules(rules: Rules) -> Self {
         let bounds = Size {
             width: 1200.0,
             height: 900.0,
@@ -105,7 +269,13 @@ impl Game {
             state: Game::main_title(&bounds, &font, high_score),
             bounds,
             font,
+            rules,
             high_score,
+            controls: Controls::new(0),
+            time_scale: TimeScale::normal(),
+            bullet_time: BulletTime::new(),
+            rewind: Rewind::new(),
+            events: Vec::new(),
         }
     }
 
@@ -118,21 +288,21 @@ impl Game {
             &Point::new(center.x, center.y + 3.0 * font.small.height()),
             "PRESS START",
         ));
-        text.extend(Game::display_score(high_score, bounds, font));
+        text.extend(Game::display_scores(&[high_score], &[], bounds, font));
         MainTitle {
             text,
             asteroids: Asteroid::field(&mut rng, bounds, 12, 0.0),
         }
     }
 
-    fn level_intro(score: u32, number: u8, bounds: &Size, font: &FontLibrary) -> State {
+    fn level_intro(scores: Vec<u32>, number: u8, bounds: &Size, font: &FontLibrary) -> State {
         let duration = 1.5;
         let title = format!("LEVEL {}", number);
         let text = (font.medium).typeset_line(Align::Center, &bounds.center(), &title);
         let mut asteroids = Level::asteroid_field(number, &bounds);
         asteroids_step(-duration, &bounds, &mut asteroids);
         LevelIntro {
-            score,
+            scores,
             number,
             text,
             asteroids,
@@ -140,28 +310,126 @@ impl Game {
         }
     }
 
-    fn display_score(score: u32, bounds: &Size, font: &FontLibrary) -> Vec<Polyline> {
-        font.small.typeset_line(
-            Align::Right,
-            &Point::new(bounds.width - 30.0, 20.0 + font.small.height()),
-            &format!("{}", score),
-        )
+    fn paused(bounds: &Size, font: &FontLibrary) -> LevelState {
+        let menu = Menu::new(vec![
+            (Choice::Resume, "RESUME"),
+            (Choice::Restart, "RESTART LEVEL"),
+            (Choice::Quit, "QUIT TO TITLE"),
+        ]);
+        Paused {
+            text: Game::pause_text(&menu, bounds, font),
+            menu,
+        }
+    }
+
+    fn pause_text(menu: &Menu<Choice>, bounds: &Size, font: &FontLibrary) -> Vec<Polyline> {
+        let center = bounds.center();
+        let mut text = (font.medium).typeset_line(Align::Center, &center, "PAUSED");
+        text.extend(menu.typeset(
+            &font.small,
+            &Point::new(center.x, center.y + 3.0 * font.small.height()),
+            bounds.width / 3.0,
+        ));
+        text
+    }
+
+    fn results(winner: usize, frags: &[u32], bounds: &Size, font: &FontLibrary) -> LevelState {
+        let center = bounds.center();
+        let title = format!("PLAYER {} WINS", winner + 1);
+        let mut text = (font.medium).typeset_line(Align::Center, &center, &title);
+        for (i, frags) in frags.iter().enumerate() {
+            text.extend(font.small.typeset_line(
+                Align::Center,
+                &Point::new(
+                    center.x,
+                    center.y + font.small.height() * (3.0 + 1.25 * i as f64),
+                ),
+                &format!("PLAYER {}  {}", i + 1, frags),
+            ));
+        }
+        Results {
+            text,
+            timer: Timer::new(8.0),
+        }
+    }
+
+    /// Scores are listed one per row, each preceded by its multiplier, if any.
+    fn display_scores(
+        scores: &[u32],
+        multipliers: &[u32],
+        bounds: &Size,
+        font: &FontLibrary,
+    ) -> Vec<Polyline> {
+        let mut text = Vec::new();
+        for (i, score) in scores.iter().enumerate() {
+            let line = match multipliers.get(i) {
+                Some(multiplier) if 1 < *multiplier => format!("{}X  {}", multiplier, score),
+                _ => format!("{}", score),
+            };
+            text.extend(font.small.typeset_line(
+                Align::Right,
+                &Point::new(
+                    bounds.width - 30.0,
+                    20.0 + font.small.height() * (1.0 + 1.25 * i as f64),
+                ),
+                &line,
+            ));
+        }
+        text
+    }
+
+    fn display_pulse(phase: f64, font: &FontLibrary) -> Polyline {
+        let center = Point::new(
+            30.0 + 0.5 * font.small.height(),
+            20.0 + 0.5 * font.small.height(),
+        );
+        let radius = 0.25 * font.small.height() * (1.0 + (1.0 - phase).powi(3));
+        let mut ring = geometry::ngon(16, radius)
+            .iter()
+            .map(|point| point.add(&center))
+            .collect::<Vec<_>>();
+        ring.push(ring[0].clone());
+        ring
+    }
+
+    fn display_charge(charge: f64, font: &FontLibrary) -> Polyline {
+        let start = Point::new(
+            30.0 + 1.5 * font.small.height(),
+            20.0 + 0.5 * font.small.height(),
+        );
+        let end = Point::new(start.x + 3.0 * font.small.height() * charge, start.y);
+        vec![start, end]
     }
 
     pub fn step(&mut self, dt: f64, controls: Controls) -> () {
+        self.step_players(dt, &[controls])
+    }
+
+    /// Step the game, with controls given for each player in order.
+    /// Menus respond to any player's controls.
+    pub fn step_players(&mut self, dt: f64, players: &[Controls]) {
         if dt <= 0.0 {
-            return ();
+            return;
         }
+        let player_dt = dt * self.time_scale.get(Class::Players);
+        let world_dt = dt * self.time_scale.get(Class::World);
+        let dt = dt * self.time_scale.global();
+        let controls = Controls::merge(players);
+        let pressed = controls.pressed(&self.controls);
+        self.controls = controls;
+        self.events.clear();
         match &mut self.state {
             MainTitle { asteroids, .. } => {
                 if controls.start() {
-                    self.state = Game::level_intro(0, 1, &self.bounds, &self.font);
+                    let scores = vec![0; self.rules.players];
+                    self.rewind = Rewind::new();
+                    self.state = Game::level_intro(scores, 1, &self.bounds, &self.font);
                 } else {
-                    asteroids_step(dt, &self.bounds, asteroids);
+                    asteroids_step(world_dt, &self.bounds, asteroids);
                 }
             }
             LevelIntro {
-                score,
+                scores,
                 number,
                 asteroids,
                 timer,
@@ -169,38 +437,69 @@ impl Game {
             } => {
                 timer.step(dt);
                 if timer.is_elapsed() {
-                    let mut level = Level::new(*number, &self.bounds);
-                    level.step(-timer.remaining(), &self.bounds, controls);
+                    let mut level = Level::new(*number, &self.bounds, &self.rules);
+                    level.step(-timer.remaining(), &self.bounds, players);
+                    self.bullet_time = BulletTime::new();
+                    self.rewind.clear();
                     self.state = ActiveLevel {
-                        score: *score,
+                        scores: mem::take(scores),
                         level,
                         state: Playing,
                     }
                 } else {
-                    asteroids_step(dt, &self.bounds, asteroids);
+                    asteroids_step(world_dt, &self.bounds, asteroids);
                 }
             }
             ActiveLevel {
-                score: _,
+                level,
+                state: state @ (Playing | Destroyed { .. }),
+                ..
+            } if controls.rewind() && self.rules.versus.is_none() => {
+                // rewinding recovers from being destroyed
+                if let Some(snapshot) = self.rewind.step_back(dt) {
+                    *level = snapshot;
+                    if !level.is_destroyed() {
+                        *state = Playing;
+                    }
+                }
+            }
+            ActiveLevel {
+                scores: _,
                 level,
                 state: state @ Playing,
             } => {
-                level.step(dt, &self.bounds, controls);
-
-                if level.asteroids().is_empty() {
-                    *state = Cleared {
-                        text: Vec::new(),
-                        timer: Timer::new(3.0),
-                    };
-                } else if level.player().is_none() {
-                    *state = Destroyed {
-                        text: Vec::new(),
-                        timer: Timer::new(7.0),
-                    };
+                if pressed.pause() {
+                    *state = Game::paused(&self.bounds, &self.font);
+                } else {
+                    if self.rules.versus.is_none() {
+                        self.rewind.record(dt, level);
+                    }
+                    let slow = controls.slow() && self.rules.versus.is_none();
+                    self.bullet_time.step(player_dt, slow);
+                    let world_dt = world_dt * self.bullet_time.scale();
+                    level.step_split(player_dt, world_dt, &self.bounds, players);
+                    self.events.append(&mut level.drain_events());
+
+                    if let Some(winner) = level.winner() {
+                        *state = Game::results(winner, &level.frags(), &self.bounds, &self.font);
+                    } else if level.asteroids().is_empty() {
+                        self.events.push(GameEvent::LevelCleared {
+                            number: level.number(),
+                        });
+                        *state = Cleared {
+                            text: Vec::new(),
+                            timer: Timer::new(3.0),
+                        };
+                    } else if level.is_destroyed() {
+                        *state = Destroyed {
+                            text: Vec::new(),
+                            timer: Timer::new(7.0),
+                        };
+                    }
                 }
             }
             ActiveLevel {
-                score,
+                scores,
                 level,
                 state: Cleared { text, timer },
             } => {
@@ -208,13 +507,14 @@ impl Game {
 
                 if timer.is_elapsed() || controls.start() {
                     self.state = Game::level_intro(
-                        *score + level.score(),
+                        add_scores(scores, &level.scores()),
                         level.number() + 1,
                         &self.bounds,
                         &self.font,
                     );
                 } else {
-                    level.step(dt, &self.bounds, controls);
+                    level.step_split(player_dt, world_dt, &self.bounds, players);
+                    self.events.append(&mut level.drain_events());
 
                     let t = timer.remaining();
                     if t <= 2.0 && 2.0 < dt + t {
@@ -227,22 +527,30 @@ impl Game {
                 }
             }
             ActiveLevel {
-                score,
+                scores,
                 level,
                 state: Destroyed { text, timer },
             } => {
                 timer.step(dt);
                 if timer.is_elapsed() {
-                    let final_score = *score + level.score();
+                    let final_score =
+                        penalize(add_scores(scores, &level.scores()), self.rewind.penalty())
+                            .iter()
+                            .sum();
                     if self.high_score < final_score {
                         self.high_score = final_score;
                     }
                     self.state = Game::main_title(&self.bounds, &self.font, self.high_score);
                 } else if controls.start() {
-                    self.state =
-                        Game::level_intro(*score, level.number(), &self.bounds, &self.font);
+                    self.state = Game::level_intro(
+                        mem::take(scores),
+                        level.number(),
+                        &self.bounds,
+                        &self.font,
+                    );
                 } else {
-                    level.step(dt, &self.bounds, controls);
+                    level.step_split(player_dt, world_dt, &self.bounds, players);
+                    self.events.append(&mut level.drain_events());
 
                     let t = timer.remaining().ceil() as u8;
                     if t <= 5 && t < (dt + timer.remaining()).ceil() as u8 {
@@ -260,14 +568,137 @@ impl Game {
                     }
                 }
             }
+            ActiveLevel {
+                level,
+                state: Results { timer, .. },
+                ..
+            } => {
+                timer.step(dt);
+                if timer.is_elapsed() || pressed.start() {
+                    self.state = Game::main_title(&self.bounds, &self.font, self.high_score);
+                } else {
+                    level.step_split(player_dt, world_dt, &self.bounds, players);
+                    self.events.append(&mut level.drain_events());
+                }
+            }
+            ActiveLevel {
+                scores,
+                level,
+                state: state @ Paused { .. },
+            } => {
+                if let Paused { menu, text } = state {
+                    if pressed.pause() {
+                        *state = Playing;
+                    } else if pressed.fire() || pressed.start() {
+                        match menu.selected() {
+                            Choice::Resume => {
+                                *state = Playing;
+                            }
+                            Choice::Restart => {
+                                self.state = Game::level_intro(
+                                    mem::take(scores),
+                                    level.number(),
+                                    &self.bounds,
+                                    &self.font,
+                                );
+                            }
+                            Choice::Quit => {
+                                let final_score = penalize(
+                                    add_scores(scores, &level.scores()),
+                                    self.rewind.penalty(),
+                                )
+                                .iter()
+                                .sum();
+                                self.high_score = self.high_score.max(final_score);
+                                self.state =
+                                    Game::main_title(&self.bounds, &self.font, self.high_score);
+                            }
+                        }
+                    } else if pressed.left() || pressed.right() {
+                        if pressed.left() {
+                            menu.previous();
+                        } else {
+                            menu.next();
+                        }
+                        *text = Game::pause_text(menu, &self.bounds, &self.font);
+                    }
+                }
+            }
         }
     }
 
-    pub fn player(&self) -> &Option<Player> {
+    /// Take the events that occurred during the most recent step.
+    /// Events that are not drained are discarded at the next step.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        mem::take(&mut self.events)
+    }
+
+    /// Set the rate at which game time passes, relative to real time.
+    pub fn set_time_scale(&mut self, scale: f64) {
+        self.time_scale.set_global(scale);
+    }
+
+    /// Override the time scale for one class of entities, or restore the
+    /// global scale with `None`.
+    pub fn set_class_time_scale(&mut self, class: Class, scale: Option<f64>) {
+        self.time_scale.set(class, scale);
+    }
+
+    pub fn time_scale(&self) -> &TimeScale {
+        &self.time_scale
+    }
+
+    /// Seconds rewound over the current run, which are deducted from scores.
+    pub fn rewound(&self) -> f64 {
+        self.rewind.used()
+    }
+
+    /// Pause an active level, e.g. when the page loses focus.
+    pub fn pause(&mut self) {
+        if let ActiveLevel {
+            state: state @ Playing,
+            ..
+        } = &mut self.state
+        {
+            *state = Game::paused(&self.bounds, &self.font);
+        }
+    }
+
+    /// A hash of the game state, which should agree between copies of a game
+    /// that have been stepped with the same time deltas and controls.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Fnv::new();
+        match &self.state {
+            MainTitle { .. } => hasher.write_u8(0),
+            LevelIntro {
+                scores,
+                number,
+                timer,
+                ..
+            } => {
+                hasher.write_u8(1);
+                scores.iter().for_each(|score| hasher.write_u32(*score));
+                hasher.write_u8(*number);
+                hasher.write_u64(timer.remaining().to_bits());
+            }
+            ActiveLevel { scores, level, .. } => {
+                hasher.write_u8(2);
+                scores.iter().for_each(|score| hasher.write_u32(*score));
+                hasher.write_u64(level.checksum());
+            }
+        }
+        hasher.finish()
+    }
+
+    pub fn bounds(&self) -> &Size {
+        &self.bounds
+    }
+
+    pub fn players(&self) -> Vec<&Player> {
         if let ActiveLevel { level, .. } = &self.state {
-            &level.player()
+            level.players().collect()
         } else {
-            &None
+            Vec::new()
         }
     }
     pub fn asteroids(&self) -> &[Asteroid] {
@@ -299,22 +730,138 @@ impl Game {
                 Playing => &[],
                 Cleared { text, .. } => &text,
                 Destroyed { text, .. } => &text,
+                Paused { text, .. } => text,
+                Results { text, .. } => text,
             },
         }
     }
+    pub fn popups(&self) -> Vec<(f64, Vec<Polyline>)> {
+        if let ActiveLevel { level, .. } = &self.state {
+            (level.popups().iter())
+                .map(|popup| {
+                    let text = (self.font.small).typeset_line(
+                        Align::Center,
+                        popup.position(),
+                        &popup.text(),
+                    );
+                    (popup.alpha(), text)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+    /// Corner brackets framing the screen while bullet time is active.
+    pub fn bullet_time_frame(&self) -> Vec<Polyline> {
+        match &self.state {
+            ActiveLevel { state: Playing, .. } if self.bullet_time.is_active() => {
+                let (inset, arm) = (16.0, 60.0);
+                let (left, top) = (inset, inset);
+                let (right, bottom) = (self.bounds.width - inset, self.bounds.height - inset);
+                [
+                    (left, top, 1.0, 1.0),
+                    (right, top, -1.0, 1.0),
+                    (right, bottom, -1.0, -1.0),
+                    (left, bottom, 1.0, -1.0),
+                ]
+                .iter()
+                .map(|&(x, y, dx, dy)| {
+                    vec![
+                        Point::new(x, y + dy * arm),
+                        Point::new(x, y),
+                        Point::new(x + dx * arm, y),
+                    ]
+                })
+                .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
     pub fn hud(&self) -> Vec<Polyline> {
         match &self.state {
             MainTitle { .. } => Vec::new(),
-            LevelIntro { score, .. } => Game::display_score(*score, &self.bounds, &self.font),
-            ActiveLevel { score, level, .. } => {
-                Game::display_score(*score + level.score(), &self.bounds, &self.font)
+            LevelIntro { scores, .. } => {
+                let scores = penalize(scores.clone(), self.rewind.penalty());
+                Game::display_scores(&scores, &[], &self.bounds, &self.font)
+            }
+            ActiveLevel {
+                scores,
+                level,
+                state,
+            } => {
+                let mut hud = if self.rules.versus.is_some() {
+                    Game::display_scores(&level.frags(), &[], &self.bounds, &self.font)
+                } else {
+                    Game::display_scores(
+                        &penalize(add_scores(scores, &level.scores()), self.rewind.penalty()),
+                        &level.multipliers(),
+                        &self.bounds,
+                        &self.font,
+                    )
+                };
+                if let Playing = state {
+                    hud.push(Game::display_pulse(level.beat_phase(), &self.font));
+                    if self.rules.versus.is_none() {
+                        hud.push(Game::display_charge(self.bullet_time.charge(), &self.font));
+                    }
+                }
+                hud
             }
         }
     }
 }
 
+fn add_scores(a: &[u32], b: &[u32]) -> Vec<u32> {
+    a.iter().zip(b.iter()).map(|(a, b)| a + b).collect()
+}
+
+/// Deduct `penalty` from each score.
+fn penalize(scores: Vec<u32>, penalty: u32) -> Vec<u32> {
+    (scores.into_iter())
+        .map(|score| score.saturating_sub(penalty))
+        .collect()
+}
+
 fn asteroids_step(dt: f64, bounds: &Size, asteroids: &mut Vec<Asteroid>) {
     for asteroid in asteroids.iter_mut() {
         asteroid.step(dt, bounds);
     }
 }
+
+#[cfg(test)]
+mod test_controls {
+    use super::*;
+
+    #[test]
+    fn test_digital() {
+        let controls = Controls::new(LEFT | THRUST);
+        assert_eq!(controls.turn(), -1.0);
+        assert_eq!(controls.thrust_amount(), 1.0);
+        assert_eq!(controls.aim(), None);
+        assert_eq!(Controls::new(LEFT | RIGHT).turn(), 0.0);
+    }
+
+    #[test]
+    fn test_analog() {
+        let controls = Controls::analog(0.3, 0.25, None, FIRE | LEFT);
+        assert_eq!(controls.turn(), 0.3);
+        assert_eq!(controls.thrust_amount(), 0.25);
+        assert!(controls.thrust() && controls.fire());
+        assert!(!controls.left() && !controls.right());
+        assert!(Controls::analog(-0.8, 0.0, None, 0).left());
+        assert_eq!(Controls::analog(2.0, f64::NAN, None, 0).turn(), 1.0);
+        assert_eq!(
+            Controls::analog(2.0, f64::NAN, None, 0).thrust_amount(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_pressed() {
+        let previous = Controls::analog(0.0, 0.0, None, 0);
+        let current = Controls::analog(-1.0, 0.0, None, START);
+        let pressed = current.pressed(&previous);
+        assert!(pressed.left() && pressed.start());
+        assert!(!current.pressed(&current).left());
+    }
+}
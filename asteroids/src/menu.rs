@@ -0,0 +1,66 @@
+use crate::geometry::{Point, Polyline};
+use crate::typography::{Align, Font};
+
+pub struct Menu<T> {
+    items: Vec<(T, &'static str)>,
+    selected: usize,
+}
+
+impl<T: Copy> Menu<T> {
+    pub fn new(items: Vec<(T, &'static str)>) -> Self {
+        Menu { items, selected: 0 }
+    }
+
+    pub fn selected(&self) -> T {
+        self.items[self.selected].0
+    }
+
+    pub fn previous(&mut self) {
+        let n = self.items.len();
+        self.selected = (self.selected + n - 1) % n;
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    /// Typeset items in a row centered on `center`, with the selected item outlined.
+    pub fn typeset(&self, font: &Font, center: &Point, spacing: f64) -> Vec<Polyline> {
+        let offset = 0.5 * (self.items.len() - 1) as f64 * spacing;
+        let mut text = Vec::new();
+        for (i, (_, label)) in self.items.iter().enumerate() {
+            let position = Point::new(center.x - offset + i as f64 * spacing, center.y);
+            let item = font.typeset_line(Align::Center, &position, label);
+            if i == self.selected {
+                text.push(outline(&item, 0.4 * font.height()));
+            }
+            text.extend(item);
+        }
+        text
+    }
+}
+
+fn outline(polylines: &[Polyline], padding: f64) -> Polyline {
+    let points = polylines.iter().flatten();
+    let (min, max) = points.fold(
+        (
+            Point::new(f64::INFINITY, f64::INFINITY),
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), point| {
+            (
+                Point::new(min.x.min(point.x), min.y.min(point.y)),
+                Point::new(max.x.max(point.x), max.y.max(point.y)),
+            )
+        },
+    );
+    let (left, top) = (min.x - padding, min.y - padding);
+    let (right, bottom) = (max.x + padding, max.y + padding);
+    vec![
+        Point::new(left, top),
+        Point::new(right, top),
+        Point::new(right, bottom),
+        Point::new(left, bottom),
+        Point::new(left, top),
+    ]
+}
@@ -1,6 +1,7 @@
 use std::f64::consts::FRAC_PI_2;
 
-use crate::geometry::{Matrix, Point, Polygon, Radians, Size, Vector};
+use crate::geometry::convex::{self, Penetration};
+use crate::geometry::{Aabb, Matrix, Point, Polygon, Radians, Size, Vector};
 use crate::iter::EdgesCycleIterator;
 
 impl Point {
@@ -17,6 +18,7 @@ impl Point {
     }
 }
 
+#[derive(Clone)]
 pub struct Movement {
     pub velocity: Vector,
     pub angular_velocity: Radians,
@@ -30,15 +32,28 @@ impl Movement {
         }
     }
 
-    pub fn from_impulse(center: &Point, contact: &Point, velocity: &Vector) -> Self {
-        let direction = contact.direction_to(center);
+    /// The change in movement of a body of `mass` and rotational `inertia`
+    /// when its point `contact` is struck so as to gain `velocity` in the
+    /// direction of the blow. The impulse is shared between linear and
+    /// angular movement according to how far off center it lands.
+    pub fn from_impulse(
+        center: &Point,
+        contact: &Point,
+        velocity: &Vector,
+        mass: f64,
+        inertia: f64,
+    ) -> Self {
         let speed = velocity.length();
-        let angle = velocity.angle_to(&direction);
-        let angular_speed = angle.signum() * (speed / contact.distance(center));
-        let t = angle.abs() / FRAC_PI_2; // rotation alpha, within range [0, 2]
+        if speed == 0.0 || mass <= 0.0 {
+            return Movement::zero();
+        }
+        let direction = velocity.normalize();
+        let arm = contact.sub(center).cross(&direction); // perpendicular lever arm
+        let turning = if 0.0 < inertia { arm / inertia } else { 0.0 };
+        let impulse = speed / (1.0 / mass + arm * turning);
         Movement {
-            velocity: direction.scale(speed * (1.0 - t)),
-            angular_velocity: angular_speed * (if 1.0 < t { 2.0 - t } else { t }),
+            velocity: direction.scale(impulse / mass),
+            angular_velocity: impulse * turning,
         }
     }
 
@@ -57,6 +72,7 @@ impl Movement {
     }
 }
 
+#[derive(Clone)]
 pub struct Placement {
     pub position: Point,
     pub rotation: Radians,
@@ -75,10 +91,19 @@ impl Placement {
     }
 
     pub fn transform_points(&self, points: &Vec<Point>) -> Vec<Point> {
-        let matrix = Matrix::new(&self.position, self.rotation, 1.0);
-        (points.iter())
-            .map(|point| point.transform(&matrix))
-            .collect()
+        self.to_matrix().transform_points(points)
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        Matrix::new(&self.position, self.rotation, 1.0)
+    }
+
+    /// The position and rotation of a matrix, discarding any scale or shear.
+    pub fn from_matrix(matrix: &Matrix) -> Self {
+        Placement {
+            position: matrix.position(),
+            rotation: matrix.rotation_angle(),
+        }
     }
 }
 
@@ -90,6 +115,11 @@ pub trait Collide {
     fn boundary(&self) -> Vec<Point>;
     fn movement(&self) -> &Movement;
     fn mass(&self) -> f64;
+    fn inertia(&self) -> f64;
+
+    fn aabb(&self) -> Aabb {
+        Aabb::enclose(&self.boundary())
+    }
 }
 
 pub fn collide<T, U>(a: &T, b: &U, elasticity: f64) -> Option<(Point, Movement, Movement)>
@@ -97,44 +127,52 @@ where
     T: Collide,
     U: Collide,
 {
-    collision_point(a, b).map(|point| collide_at_point(&point, a, b, elasticity))
+    contact(a, b).map(|(point, normal)| collide_at_contact(&point, &normal, a, b, elasticity))
 }
 
-fn collision_point<T, U>(a: &T, b: &U) -> Option<Point>
+/// The point of contact between two colliding objects, with the contact
+/// normal pointing from `a` toward `b`.
+fn contact<T, U>(a: &T, b: &U) -> Option<(Point, Vector)>
 where
     T: Collide,
     U: Collide,
 {
     // do circles overlap?
     if a.center().distance(b.center()) < a.radius() + b.radius() {
+        let a_boundary = a.boundary();
+        let b_boundary = b.boundary();
         // do polygons overlap?
-        if let Some(point) =
-            Point::mean(&Polygon(&a.boundary()).intersections(b.boundary().iter().edges_cycle()))
-        {
-            // are movements colliding?
-            let is_collision = {
-                let a_speed = a.movement().velocity.length();
-                let b_speed = b.movement().velocity.length();
-                let a_is_facing =
-                    a.movement().velocity.angle_between(&b.center().sub(&point)) < FRAC_PI_2;
-                let b_is_facing =
-                    b.movement().velocity.angle_between(&a.center().sub(&point)) < FRAC_PI_2;
-
-                a_is_facing && b_is_facing
-                    || a_is_facing && b_speed < a_speed
-                    || b_is_facing && a_speed < b_speed
-            };
-
-            if is_collision {
-                return Some(point);
+        if let Some(Penetration { normal, .. }) = convex::penetration(&a_boundary, &b_boundary) {
+            let point = contact_point(&a_boundary, &b_boundary)?;
+            // are movements closing along the normal?
+            let closing_speed = (b.movement().velocity)
+                .sub(&a.movement().velocity)
+                .dot(&normal);
+            if closing_speed < 0.0 {
+                return Some((point, normal));
             }
         }
     }
     None
 }
 
-fn collide_at_point<T, U>(
+fn contact_point(a: &[Point], b: &[Point]) -> Option<Point> {
+    let a_polygon = a.to_vec();
+    let b_polygon = b.to_vec();
+    Point::mean(&Polygon(&a_polygon).intersections(b.iter().edges_cycle())).or_else(|| {
+        // one boundary lies within the other
+        let contained = (a.iter())
+            .filter(|point| Polygon(&b_polygon).contains(point))
+            .chain(b.iter().filter(|point| Polygon(&a_polygon).contains(point)))
+            .cloned()
+            .collect();
+        Point::mean(&contained)
+    })
+}
+
+fn collide_at_contact<T, U>(
     point: &Point,
+    normal: &Vector,
     a: &T,
     b: &U,
     elasticity: f64,
@@ -145,25 +183,25 @@ where
 {
     let inelastic_movement =
         (a.movement()).interpolate(b.movement(), b.mass() / (a.mass() + b.mass()));
-
     (
         point.clone(),
-        inelastic_movement.interpolate(&collision_movement(point, a, b), elasticity),
-        inelastic_movement.interpolate(&collision_movement(point, b, a), elasticity),
+        inelastic_movement.interpolate(&collision_movement(point, normal, a, b), elasticity),
+        inelastic_movement.interpolate(&collision_movement(point, normal, b, a), elasticity),
     )
 }
 
-fn collision_movement<T, U>(point: &Point, a: &T, b: &U) -> Movement
+fn collision_movement<T, U>(point: &Point, normal: &Vector, a: &T, b: &U) -> Movement
 where
     T: Collide,
     U: Collide,
 {
-    let reflection = (a.movement().velocity).reflect(&b.center().direction_to(a.center()));
+    let reflection = (a.movement().velocity).reflect(normal);
     let contact_velocity = b.movement().velocity.add(&tangential_velocity(
         &point.sub(b.center()),
         b.movement().angular_velocity,
     ));
-    let impact = Movement::from_impulse(a.center(), point, &contact_velocity);
+    let impact =
+        Movement::from_impulse(a.center(), point, &contact_velocity, a.mass(), a.inertia());
     let t = b.mass() / (a.mass() + b.mass());
     Movement {
         velocity: (a.movement().velocity)
@@ -183,3 +221,36 @@ fn tangential_velocity(radial: &Vector, angular_velocity: Radians) -> Vector {
 fn interpolate(a: f64, b: f64, t: f64) -> f64 {
     (a * (1.0 - t)) + (b * t)
 }
+
+#[cfg(test)]
+mod test_motion {
+    use super::*;
+
+    #[test]
+    fn test_from_impulse() {
+        let center = Point::origin();
+        let velocity = Vector::new(0.0, 10.0);
+        // through the center, all linear
+        let movement = Movement::from_impulse(&center, &Point::new(0.0, -5.0), &velocity, 2.0, 8.0);
+        assert_eq!(movement.velocity, velocity);
+        assert_eq!(movement.angular_velocity, 0.0);
+        // off center, shared with rotation so the contact point gains `velocity`
+        let contact = Point::new(2.0, 0.0);
+        let movement = Movement::from_impulse(&center, &contact, &velocity, 2.0, 8.0);
+        assert_eq!(movement.velocity, Vector::new(0.0, 5.0));
+        assert_eq!(movement.angular_velocity, 2.5);
+        let contact_speed = movement.velocity.y + movement.angular_velocity * contact.x;
+        assert_eq!(contact_speed, velocity.y);
+    }
+
+    #[test]
+    fn test_placement_matrix() {
+        let placement = Placement {
+            position: Point::new(3.0, 4.0),
+            rotation: 2.0,
+        };
+        let roundtrip = Placement::from_matrix(&placement.to_matrix());
+        assert!(roundtrip.position.distance(&placement.position) < 1e-9);
+        assert!((roundtrip.rotation - placement.rotation).abs() < 1e-9);
+    }
+}
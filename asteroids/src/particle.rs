@@ -7,6 +7,7 @@ use crate::geometry::{Point, Radians, Size, Vector};
 use crate::motion::{Movement, Placement};
 use crate::util::Timer;
 
+#[derive(Clone)]
 pub struct Particle {
     placement: Placement,
     movement: Movement,
@@ -1,10 +1,10 @@
 use rand_pcg::Pcg32;
-use std::f64::consts::FRAC_PI_2;
+use std::f64::consts::{FRAC_PI_2, PI};
 
 use crate::asteroid::Asteroid;
 use crate::blast::Blast;
 use crate::geometry;
-use crate::geometry::{Point, Size, Vector};
+use crate::geometry::{Point, Polygon, Radians, Size, Vector};
 use crate::iter::{EdgesCycleIterator, EdgesIterator};
 use crate::motion;
 use crate::motion::{Collide, Movement, Placement};
@@ -38,6 +38,8 @@ const TURNING_SPEED: f64 = 0.5; // radians / second
 const THRUST_SPEED: f64 = 35.0; // px / second
 const POSITION_FRICTION: f64 = 0.98;
 const ROTATION_FRICTION: f64 = 0.92;
+const AIM_RESPONSE: f64 = 8.0; // (radians / second) / radian
+const WALL_ELASTICITY: f64 = 0.6;
 
 const FIRING_INTERVAL: f64 = 1.0 / 6.0; // seconds (6 hz)
 const BLAST_SPEED: f64 = 800.0; // px / second
@@ -46,43 +48,55 @@ const THRUSTING_INTERVAL: f64 = 1.0 / 12.0; // seconds (12 hz)
 const EXHAUST_SPEED: f64 = 120.0; // px / second
 const EXHAUST_MAX_AGE: f64 = 0.2; // seconds
 
+#[derive(Clone)]
 struct Spaceship {
     radius: f64,
     hull: Vec<Point>,
     interior: Vec<Point>,
     shield: Vec<Point>,
     nozzle: Point,
+    inertia: f64,
 }
 
 impl Spaceship {
     fn new(radius: f64) -> Self {
         let factor = radius / 22.0;
+        let hull: Vec<Point> = HULL.iter().map(|point| point.scale(factor)).collect();
+        // spread the mass evenly over the hull, rotating about the origin
+        let inertia =
+            SPACESHIP_MASS * Polygon(&hull).inertia(&Point::origin()) / Polygon(&hull).area();
         Spaceship {
             radius,
-            hull: HULL.iter().map(|point| point.scale(factor)).collect(),
+            hull,
             interior: INTERIOR.iter().map(|point| point.scale(factor)).collect(),
             shield: geometry::ngon(16, radius + 1.0),
             nozzle: NOZZLE.scale(factor),
+            inertia,
         }
     }
 }
 
+#[derive(Clone)]
 enum Aux {
     Off,
     Firing { interval: Interval },
     Shielding { delay: Timer },
 }
 
+#[derive(Clone)]
 enum Engine {
     Idle,
     Thrusting { interval: Interval },
 }
 
 pub struct Impact {
+    pub point: Point,
+    pub speed: f64,
     pub destroyed: bool,
     pub particles: Vec<Particle>,
 }
 
+#[derive(Clone)]
 pub struct Player {
     placement: Placement,
     movement: Movement,
@@ -90,6 +104,7 @@ pub struct Player {
     aux: Aux,
     engine: Engine,
     exhaust: Vec<Timer>,
+    confined: bool,
 }
 
 impl Player {
@@ -107,9 +122,29 @@ impl Player {
             aux: Aux::Off,
             engine: Engine::Idle,
             exhaust: Vec::new(),
+            confined: false,
         }
     }
 
+    /// Keep the player within bounds, bouncing off the edges rather than
+    /// wrapping around to the opposite side.
+    pub fn confined(mut self) -> Self {
+        self.confined = true;
+        self
+    }
+
+    pub fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    /// The hull, interior and, when shielding, the shield outlines,
+    /// untransformed by the placement.
+    pub fn outlines(&self) -> (&Vec<Point>, &Vec<Point>, Option<&Vec<Point>>) {
+        let spaceship = &self.spaceship;
+        let shield = Some(&spaceship.shield).filter(|_| self.is_shielding());
+        (&spaceship.hull, &spaceship.interior, shield)
+    }
+
     pub fn hull(&self) -> Vec<Point> {
         self.placement.transform_points(&self.spaceship.hull)
     }
@@ -118,7 +153,14 @@ impl Player {
         self.placement.transform_points(&self.spaceship.interior)
     }
 
-    fn is_shielding(&self) -> bool {
+    pub fn is_thrusting(&self) -> bool {
+        match &self.engine {
+            Engine::Thrusting { .. } => true,
+            Engine::Idle => false,
+        }
+    }
+
+    pub fn is_shielding(&self) -> bool {
         match &self.aux {
             Aux::Shielding { delay } if delay.is_elapsed() => true,
             _ => false,
@@ -151,18 +193,18 @@ impl Player {
     }
 
     pub fn step(&mut self, dt: f64, bounds: &Size, controls: Controls) -> () {
-        let rotation_thrust = match (controls.left(), controls.right()) {
-            (true, false) => -TURNING_SPEED * dt,
-            (false, true) => TURNING_SPEED * dt,
-            _ => 0.0,
+        let turn = match controls.aim() {
+            Some(aim) => self.turn_toward(aim),
+            None => controls.turn(),
         };
+        let rotation_thrust = turn * TURNING_SPEED * dt;
 
         let rotation = self.placement.rotation
             + (self.movement.angular_velocity * ROTATION_FRICTION * dt)
             + rotation_thrust;
 
         let position_thrust = if controls.thrust() {
-            Vector::from_polar(THRUST_SPEED * dt, rotation)
+            Vector::from_polar(controls.thrust_amount() * THRUST_SPEED * dt, rotation)
         } else {
             Vector::new(0.0, 0.0)
         };
@@ -175,7 +217,11 @@ impl Player {
         self.movement.angular_velocity = (rotation - self.placement.rotation) / dt;
         self.placement.position = position;
         self.placement.rotation = rotation;
-        self.placement.wrap_position(bounds);
+        if self.confined {
+            self.bounce_edges(bounds);
+        } else {
+            self.placement.wrap_position(bounds);
+        }
 
         // aux
         if controls.shield() {
@@ -221,6 +267,37 @@ impl Player {
         self.exhaust.retain(|timer| !timer.is_elapsed());
     }
 
+    /// The turn, within range [-1, 1], that brings rotation to `aim` without
+    /// overshooting it.
+    fn turn_toward(&self, aim: Radians) -> f64 {
+        let difference = (aim - self.placement.rotation + PI).rem_euclid(2.0 * PI) - PI;
+        let max_speed = TURNING_SPEED / (1.0 - ROTATION_FRICTION);
+        let target_speed = (difference * AIM_RESPONSE).clamp(-max_speed, max_speed);
+        let turn =
+            (target_speed - self.movement.angular_velocity * ROTATION_FRICTION) / TURNING_SPEED;
+        turn.clamp(-1.0, 1.0)
+    }
+
+    fn bounce_edges(&mut self, bounds: &Size) {
+        let radius = self.spaceship.radius;
+        let position = &mut self.placement.position;
+        let velocity = &mut self.movement.velocity;
+        if position.x < radius {
+            position.x = radius;
+            velocity.x = velocity.x.abs() * WALL_ELASTICITY;
+        } else if bounds.width - radius < position.x {
+            position.x = bounds.width - radius;
+            velocity.x = -velocity.x.abs() * WALL_ELASTICITY;
+        }
+        if position.y < radius {
+            position.y = radius;
+            velocity.y = velocity.y.abs() * WALL_ELASTICITY;
+        } else if bounds.height - radius < position.y {
+            position.y = bounds.height - radius;
+            velocity.y = -velocity.y.abs() * WALL_ELASTICITY;
+        }
+    }
+
     pub fn fire_blast(&mut self) -> Option<Blast> {
         match &mut self.aux {
             Aux::Firing { interval } => interval.next().map(|_| {
@@ -240,6 +317,8 @@ impl Player {
                 &self.placement.position,
                 &impact.point,
                 &blast.velocity().normalize().scale(impact.speed),
+                SPACESHIP_MASS,
+                self.spaceship.inertia,
             ));
             Some(self.impact(rng, &impact.point, impact.speed))
         } else {
@@ -281,6 +360,8 @@ impl Player {
                 delay: Timer::new(speed * 0.002),
             };
             Impact {
+                point: point.clone(),
+                speed,
                 destroyed: false,
                 particles,
             }
@@ -306,6 +387,8 @@ impl Player {
             particles.append(&mut dispersion.explode(rng, (self.interior().iter()).edges()));
 
             Impact {
+                point: point.clone(),
+                speed,
                 destroyed: true,
                 particles,
             }
@@ -333,4 +416,46 @@ impl Collide for Player {
     fn mass(&self) -> f64 {
         SPACESHIP_MASS
     }
+    fn inertia(&self) -> f64 {
+        self.spaceship.inertia
+    }
+}
+
+#[cfg(test)]
+mod test_player {
+    use super::*;
+
+    fn bounds() -> Size {
+        Size {
+            width: 1200.0,
+            height: 900.0,
+        }
+    }
+
+    #[test]
+    fn test_aim() {
+        for &aim in [0.0, 2.5, -2.5, 10.0].iter() {
+            let mut player = Player::new(bounds().center());
+            for _ in 0..180 {
+                player.step(
+                    1.0 / 60.0,
+                    &bounds(),
+                    Controls::analog(0.0, 0.0, Some(aim), 0),
+                );
+            }
+            let rotation = player.placement().rotation;
+            let difference = (aim - rotation + PI).rem_euclid(2.0 * PI) - PI;
+            assert!(difference.abs() < 0.01, "{} -> {}", aim, rotation);
+        }
+    }
+
+    #[test]
+    fn test_partial_thrust() {
+        let mut half = Player::new(bounds().center());
+        let mut full = Player::new(bounds().center());
+        half.step(0.1, &bounds(), Controls::analog(0.0, 0.5, None, 0));
+        full.step(0.1, &bounds(), Controls::new(4));
+        let speed = full.movement().velocity.length();
+        assert!((half.movement().velocity.length() - 0.5 * speed).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,109 @@
+use crate::history::History;
+use crate::level::Level;
+
+const SNAPSHOT_INTERVAL: f64 = 1.0 / 30.0; // seconds
+const REWIND_DURATION: f64 = 5.0; // seconds
+const REWIND_PENALTY: f64 = 100.0; // points per second rewound
+
+/// Recent snapshots of a level, which can be played back in reverse, along
+/// with how much time has been rewound over a run.
+pub struct Rewind {
+    history: History<Level>,
+    elapsed: f64,
+    rewinding: bool,
+    used: f64,
+}
+
+impl Rewind {
+    pub fn new() -> Self {
+        Rewind {
+            history: History::new((REWIND_DURATION / SNAPSHOT_INTERVAL).ceil() as usize),
+            elapsed: 0.0,
+            rewinding: false,
+            used: 0.0,
+        }
+    }
+
+    /// Forget the recorded snapshots, e.g. when a new level begins.
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.elapsed = 0.0;
+    }
+
+    /// Record `level` if a snapshot is due after `dt` has passed.
+    pub fn record(&mut self, dt: f64, level: &Level) {
+        if self.rewinding {
+            self.rewinding = false;
+            self.elapsed = 0.0;
+        }
+        self.elapsed += dt;
+        if SNAPSHOT_INTERVAL <= self.elapsed || self.history.is_empty() {
+            self.elapsed = 0.0;
+            let tick = self.history.latest().map_or(0, |tick| tick + 1);
+            self.history.push(tick, level.clone());
+        }
+    }
+
+    /// Play back `dt` in reverse, returning the snapshot reached, if any.
+    pub fn step_back(&mut self, dt: f64) -> Option<Level> {
+        if !self.rewinding {
+            self.rewinding = true;
+            self.elapsed = 0.0;
+        }
+        if self.history.is_empty() {
+            return None;
+        }
+        self.used += dt;
+        self.elapsed += dt;
+        let mut level = None;
+        while SNAPSHOT_INTERVAL <= self.elapsed {
+            self.elapsed -= SNAPSHOT_INTERVAL;
+            match self.history.pop() {
+                Some((_, snapshot)) => level = Some(snapshot),
+                None => break,
+            }
+        }
+        level
+    }
+
+    /// Seconds rewound over the run.
+    pub fn used(&self) -> f64 {
+        self.used
+    }
+
+    /// Points deducted from each score for the time rewound.
+    pub fn penalty(&self) -> u32 {
+        (self.used * REWIND_PENALTY).round() as u32
+    }
+}
+
+#[cfg(test)]
+mod test_rewind {
+    use super::*;
+    use crate::geometry::Size;
+    use crate::level::Rules;
+    use crate::Controls;
+
+    #[test]
+    fn test_step_back() {
+        let bounds = Size {
+            width: 1200.0,
+            height: 900.0,
+        };
+        let mut level = Level::new(1, &bounds, &Rules::single());
+        let mut rewind = Rewind::new();
+        let mut checksums = Vec::new();
+        for _ in 0..60 {
+            rewind.record(SNAPSHOT_INTERVAL, &level);
+            checksums.push(level.checksum());
+            level.step(SNAPSHOT_INTERVAL, &bounds, &[Controls::new(4 | 8)]);
+        }
+        let level = rewind.step_back(10.5 * SNAPSHOT_INTERVAL).unwrap();
+        assert_eq!(level.checksum(), checksums[60 - 10]);
+        assert!(0 < rewind.penalty());
+
+        assert!(rewind.step_back(REWIND_DURATION).is_some());
+        assert!(rewind.step_back(SNAPSHOT_INTERVAL).is_none());
+        assert!((rewind.used() - (10.5 * SNAPSHOT_INTERVAL + REWIND_DURATION)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,107 @@
+const SLOWEST_PERIOD: f64 = 1.0; // seconds, with the level's starting mass
+const FASTEST_PERIOD: f64 = 0.25; // seconds, as remaining mass approaches zero
+
+pub struct Beat {
+    pub high: bool,
+    pub period: f64,
+}
+
+/// Alternating low and high beats, with a tempo that increases as the mass of
+/// the asteroid field decreases.
+#[derive(Clone)]
+pub struct Rhythm {
+    starting_mass: f64,
+    period: f64,
+    phase: f64,
+    high: bool,
+}
+
+impl Rhythm {
+    pub fn new(starting_mass: f64) -> Self {
+        Rhythm {
+            starting_mass,
+            period: SLOWEST_PERIOD,
+            phase: 0.0,
+            high: true,
+        }
+    }
+
+    pub fn period(&self, remaining_mass: f64) -> f64 {
+        let fraction = if 0.0 < self.starting_mass {
+            (remaining_mass / self.starting_mass).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        FASTEST_PERIOD + (SLOWEST_PERIOD - FASTEST_PERIOD) * fraction
+    }
+
+    /// Position within the current beat, within range [0, 1).
+    pub fn phase(&self) -> f64 {
+        self.phase.fract()
+    }
+
+    /// Advance by `dt`, giving at most one beat per step. Beats owed by a
+    /// step longer than a period follow on the next steps, so that the
+    /// alternation of low and high beats keeps time with the tempo.
+    pub fn step(&mut self, dt: f64, remaining_mass: f64) -> Option<Beat> {
+        self.period = self.period(remaining_mass);
+        self.phase += dt / self.period;
+        if 1.0 <= self.phase {
+            self.phase -= 1.0;
+            self.high = !self.high;
+            Some(Beat {
+                high: self.high,
+                period: self.period,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_rhythm {
+    use super::*;
+
+    #[test]
+    fn test_period() {
+        let rhythm = Rhythm::new(1000.0);
+        assert_eq!(rhythm.period(1000.0), SLOWEST_PERIOD);
+        assert_eq!(rhythm.period(2000.0), SLOWEST_PERIOD);
+        assert_eq!(rhythm.period(0.0), FASTEST_PERIOD);
+        assert_eq!(
+            rhythm.period(500.0),
+            0.5 * (SLOWEST_PERIOD + FASTEST_PERIOD)
+        );
+    }
+
+    #[test]
+    fn test_alternate() {
+        let mut rhythm = Rhythm::new(1000.0);
+        assert!(rhythm.step(0.5, 1000.0).is_none());
+        assert_eq!(rhythm.phase(), 0.5);
+        assert_eq!(rhythm.step(0.5, 1000.0).map(|beat| beat.high), Some(false));
+        assert_eq!(rhythm.step(1.0, 1000.0).map(|beat| beat.high), Some(true));
+    }
+
+    #[test]
+    fn test_long_step() {
+        let mut rhythm = Rhythm::new(1000.0);
+        assert_eq!(rhythm.step(2.5, 1000.0).map(|beat| beat.high), Some(false));
+        assert_eq!(rhythm.phase(), 0.5);
+        assert_eq!(rhythm.step(0.0, 1000.0).map(|beat| beat.high), Some(true));
+        assert!(rhythm.step(0.0, 1000.0).is_none());
+        assert_eq!(rhythm.step(0.5, 1000.0).map(|beat| beat.high), Some(false));
+        assert_eq!(rhythm.phase(), 0.0);
+    }
+
+    #[test]
+    fn test_tempo() {
+        let mut rhythm = Rhythm::new(1000.0);
+        let beats = (0..100)
+            .filter_map(|_| rhythm.step(0.05, 0.0))
+            .collect::<Vec<_>>();
+        assert_eq!(beats.len(), 20);
+        assert!(beats.iter().all(|beat| beat.period == FASTEST_PERIOD));
+    }
+}
@@ -0,0 +1,131 @@
+/// Classes of entities whose time scale can be set apart from the global one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Class {
+    /// Spaceships and their blasts
+    Players,
+    /// Asteroids, particles and any other blasts
+    World,
+}
+
+/// Factors by which time passes in the game, relative to real time.
+#[derive(Clone)]
+pub struct TimeScale {
+    global: f64,
+    players: Option<f64>,
+    world: Option<f64>,
+}
+
+impl TimeScale {
+    pub fn normal() -> Self {
+        TimeScale {
+            global: 1.0,
+            players: None,
+            world: None,
+        }
+    }
+
+    pub fn global(&self) -> f64 {
+        self.global
+    }
+
+    pub fn set_global(&mut self, scale: f64) {
+        self.global = scale.max(0.0);
+    }
+
+    /// Override the global scale for one class of entities, or restore it
+    /// with `None`.
+    pub fn set(&mut self, class: Class, scale: Option<f64>) {
+        let scale = scale.map(|scale| scale.max(0.0));
+        match class {
+            Class::Players => self.players = scale,
+            Class::World => self.world = scale,
+        }
+    }
+
+    pub fn get(&self, class: Class) -> f64 {
+        match class {
+            Class::Players => self.players,
+            Class::World => self.world,
+        }
+        .unwrap_or(self.global)
+    }
+}
+
+const BULLET_TIME_SCALE: f64 = 0.3;
+const BULLET_TIME_CAPACITY: f64 = 3.0; // seconds
+const BULLET_TIME_RECHARGE: f64 = 0.2; // seconds per second
+
+/// An ability to slow the world while the players move at full speed, which
+/// drains while in use and slowly recharges.
+#[derive(Clone)]
+pub struct BulletTime {
+    charge: f64,
+    active: bool,
+}
+
+impl BulletTime {
+    pub fn new() -> Self {
+        BulletTime {
+            charge: BULLET_TIME_CAPACITY,
+            active: false,
+        }
+    }
+
+    /// Step by the players' `dt`, using the ability if `held`.
+    pub fn step(&mut self, dt: f64, held: bool) {
+        self.active = held && 0.0 < self.charge;
+        if self.active {
+            self.charge = (self.charge - dt).max(0.0);
+        } else {
+            self.charge = (self.charge + BULLET_TIME_RECHARGE * dt).min(BULLET_TIME_CAPACITY);
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// The remaining charge, within range [0, 1].
+    pub fn charge(&self) -> f64 {
+        self.charge / BULLET_TIME_CAPACITY
+    }
+
+    /// The factor applied to the world's time scale.
+    pub fn scale(&self) -> f64 {
+        if self.active {
+            BULLET_TIME_SCALE
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_time {
+    use super::*;
+
+    #[test]
+    fn test_override() {
+        let mut scale = TimeScale::normal();
+        scale.set_global(0.5);
+        scale.set(Class::Players, Some(2.0));
+        assert_eq!(scale.get(Class::Players), 2.0);
+        assert_eq!(scale.get(Class::World), 0.5);
+        scale.set(Class::Players, None);
+        assert_eq!(scale.get(Class::Players), 0.5);
+    }
+
+    #[test]
+    fn test_bullet_time() {
+        let mut bullet_time = BulletTime::new();
+        bullet_time.step(1.0, true);
+        assert!(bullet_time.is_active());
+        assert_eq!(bullet_time.scale(), BULLET_TIME_SCALE);
+        bullet_time.step(BULLET_TIME_CAPACITY, true);
+        assert_eq!(bullet_time.charge(), 0.0);
+        bullet_time.step(1.0, true);
+        assert!(!bullet_time.is_active());
+        bullet_time.step(1.0, false);
+        assert!(0.0 < bullet_time.charge());
+    }
+}
@@ -1,3 +1,6 @@
+use std::hash::Hasher;
+
+#[derive(Clone)]
 pub struct Timer(f64);
 
 impl Timer {
@@ -18,6 +21,7 @@ impl Timer {
     }
 }
 
+#[derive(Clone)]
 pub struct Interval {
     period: f64,
     t: f64,
@@ -49,6 +53,29 @@ impl Iterator for Interval {
     }
 }
 
+/// FNV-1a, a simple hash that is stable across runs and platforms, for
+/// comparing simulation state.
+pub struct Fnv(u64);
+
+impl Fnv {
+    pub fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
 #[cfg(test)]
 mod test_interval {
     use super::*;
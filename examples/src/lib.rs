@@ -1,5 +1,5 @@
 use core::f64::consts::PI;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg32;
 use wasm_bindgen::prelude::wasm_bindgen;
 
@@ -207,7 +207,10 @@ impl SplitPolygon {
         let mut list = PathList::new();
         let (a, b) = &self.line;
         list.push(&mut vec![a.clone(), b.clone()], 0.5, PathEnd::Open);
-        for mut polygon in Polygon(&self.polygon).split(a, b) {
+        let polygons = Polygon(&self.polygon)
+            .split(a, b)
+            .unwrap_or_else(|_| vec![self.polygon.clone()]);
+        for mut polygon in polygons {
             render_circle(&Circle::enclose(&polygon), &mut list);
             list.push(&mut polygon, 1.0, PathEnd::Closed);
         }
@@ -254,7 +257,13 @@ impl Impulse {
 
     pub fn fire(&mut self) -> () {
         if let Some((contact, velocity)) = &self.pending {
-            let impulse = Movement::from_impulse(&self.placement.position, contact, velocity);
+            let impulse = Movement::from_impulse(
+                &self.placement.position,
+                contact,
+                velocity,
+                Polygon(&self.polygon).area(),
+                Polygon(&self.polygon).inertia(&Point::origin()),
+            );
             self.movement = self.movement.add(&impulse);
             self.pending = None;
         }
@@ -424,6 +433,9 @@ impl Collide for Disk {
     fn mass(&self) -> f64 {
         self.radius * self.radius
     }
+    fn inertia(&self) -> f64 {
+        self.mass() * self.radius * self.radius / 2.0
+    }
 }
 
 // 08
@@ -575,3 +587,43 @@ pub fn asteroid_area(seed: u32, rows: u32, cols: u32) -> PathList {
 
     list
 }
+
+// 12
+
+#[wasm_bindgen]
+pub struct Craters {
+    asteroids: Vec<Asteroid>,
+}
+
+#[wasm_bindgen]
+impl Craters {
+    pub fn new() -> Self {
+        let center = Point::new(BOUNDS.width, BOUNDS.height).scale(0.5);
+        let mut rng = Pcg32::seed_from_u64(1979);
+        let polygon = (geometry::ngon(24, 200.0).iter())
+            .map(|point| point.scale(rng.gen_range(0.8, 1.0)).add(&center))
+            .collect();
+        Craters {
+            asteroids: vec![Asteroid::from_polygon(&polygon)],
+        }
+    }
+
+    pub fn carve(&mut self, x: f64, y: f64) {
+        let center = Point::new(x, y);
+        self.asteroids = (self.asteroids.iter())
+            .flat_map(|asteroid| asteroid.crater(&center, 30.0))
+            .collect();
+    }
+
+    pub fn render(&self) -> PathList {
+        let mut list = PathList::new();
+        render::asteroids(&self.asteroids, &mut list);
+        list
+    }
+}
+
+impl Default for Craters {
+    fn default() -> Self {
+        Craters::new()
+    }
+}
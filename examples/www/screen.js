@@ -22,9 +22,9 @@ function screen(memory, screenCanvas) {
         // draw to canvas
         const length = list.length();
         const paths = new Uint32Array(memory.buffer, list.paths(), length * 2);
-        const alphas = new Float64Array(memory.buffer, list.alphas(), length);
+        const alphas = new Float32Array(memory.buffer, list.alphas(), length);
         const ends = new Uint8Array(memory.buffer, list.ends(), length);
-        const points = new Float64Array(memory.buffer, list.points(), list.points_length() * 2);
+        const points = new Float32Array(memory.buffer, list.points(), list.points_length() * 2);
 
         context.clearRect(-width, -height, 3 * width, 3 * height);
         for (let i = 0; i < length; i += 1) {
@@ -0,0 +1,15 @@
+//! Relay for lockstep netplay, e.g. `relay 127.0.0.1:7878 2`.
+
+use std::env;
+use std::io;
+
+use net::relay::Relay;
+
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:7878".to_string());
+    let players = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(2);
+    let relay = Relay::bind(&address, players)?;
+    println!("relaying {} players on {}", players, relay.local_addr()?);
+    relay.run()
+}
@@ -0,0 +1,186 @@
+//! Lockstep netplay: peers exchange the `Controls` for each tick, through a
+//! relay, and step identical copies of a `Game`. Each peer also shares a
+//! checksum of its game state after every tick, so a divergence is detected
+//! on the tick it occurs.
+//!
+//! Alternatively, `Rollback` simulates a `Level` ahead of remote input, and
+//! corrects mispredictions by restoring an earlier snapshot.
+
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use asteroids::{Controls, Game};
+
+mod lockstep;
+mod message;
+pub mod relay;
+mod rollback;
+mod transport;
+
+pub use lockstep::{Desync, Lockstep};
+pub use message::Message;
+pub use rollback::Rollback;
+pub use transport::{MemoryTransport, TcpTransport, Transport};
+
+/// The fixed time step of a networked game, in seconds.
+pub const TICK: f64 = 1.0 / 60.0;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Desync(Desync),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<Desync> for Error {
+    fn from(desync: Desync) -> Self {
+        Error::Desync(desync)
+    }
+}
+
+pub struct Session<T> {
+    transport: T,
+    lockstep: Lockstep,
+}
+
+impl<T: Transport> Session<T> {
+    pub fn new(transport: T, player: usize, players: usize, delay: u32) -> Self {
+        Session {
+            transport,
+            lockstep: Lockstep::new(player, players, delay),
+        }
+    }
+
+    /// Wait for the relay to assign this peer a player index.
+    pub fn join(mut transport: T, delay: u32) -> Result<Self, Error> {
+        loop {
+            match transport.receive()? {
+                Some(Message::Welcome { player, players }) => {
+                    return Ok(Session::new(
+                        transport,
+                        player as usize,
+                        players as usize,
+                        delay,
+                    ));
+                }
+                Some(_) => (),
+                None => thread::sleep(Duration::from_millis(1)),
+            }
+        }
+    }
+
+    pub fn player(&self) -> usize {
+        self.lockstep.player()
+    }
+
+    pub fn players(&self) -> usize {
+        self.lockstep.players()
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.lockstep.tick()
+    }
+
+    /// Send local `input`, then step `game` through every tick whose inputs
+    /// have all arrived. Returns the number of ticks stepped.
+    pub fn step(&mut self, game: &mut Game, input: u32) -> Result<u32, Error> {
+        if let Some(message) = self.lockstep.schedule(input) {
+            self.transport.send(&message)?;
+        }
+        while let Some(message) = self.transport.receive()? {
+            self.lockstep.receive(&message)?;
+        }
+        let mut ticks = 0;
+        while let Some(inputs) = self.lockstep.inputs() {
+            let controls = inputs.iter().map(|&input| Controls::new(input));
+            game.step_players(TICK, &controls.collect::<Vec<_>>());
+            let message = self.lockstep.advance(game.checksum())?;
+            self.transport.send(&message)?;
+            ticks += 1;
+        }
+        Ok(ticks)
+    }
+}
+
+#[cfg(test)]
+mod test_session {
+    use super::*;
+    use asteroids::Rules;
+    use relay::Relay;
+
+    const START: u32 = 32;
+    const THRUST_FIRE: u32 = 4 | 8;
+
+    fn input(player: usize, frame: u32) -> u32 {
+        match frame {
+            0..=5 => START,
+            _ if (frame / 20) % 2 == player as u32 => THRUST_FIRE,
+            _ => 1 << player,
+        }
+    }
+
+    /// Step both peers until each has reached `ticks`, failing on any desync.
+    fn run<T: Transport>(a: &mut Session<T>, b: &mut Session<T>, ticks: u32) -> (Game, Game) {
+        let mut games = (
+            Game::with_rules(Rules::coop(2, true)),
+            Game::with_rules(Rules::coop(2, true)),
+        );
+        let mut frame = 0;
+        while a.tick() < ticks || b.tick() < ticks {
+            a.step(&mut games.0, input(0, frame)).unwrap();
+            b.step(&mut games.1, input(1, frame)).unwrap();
+            frame += 1;
+        }
+        games
+    }
+
+    #[test]
+    fn test_memory() {
+        let (x, y) = MemoryTransport::pair(3);
+        let mut a = Session::new(x, 0, 2, 4);
+        let mut b = Session::new(y, 1, 2, 4);
+        let (game, _) = run(&mut a, &mut b, 600);
+        assert!(600 <= a.tick() && 600 <= b.tick());
+        let initial = Game::with_rules(Rules::coop(2, true));
+        assert_ne!(game.checksum(), initial.checksum());
+    }
+
+    #[test]
+    fn test_desync() {
+        let (x, y) = MemoryTransport::pair(0);
+        let mut a = Session::new(x, 0, 2, 2);
+        let mut b = Session::new(y, 1, 2, 2);
+        let mut game_a = Game::with_rules(Rules::coop(2, true));
+        let mut game_b = Game::with_rules(Rules::coop(3, true));
+        let mut result = Ok(0);
+        for frame in 0..600 {
+            result = (a.step(&mut game_a, input(0, frame)))
+                .and_then(|_| b.step(&mut game_b, input(1, frame)));
+            if result.is_err() {
+                break;
+            }
+        }
+        assert!(matches!(result, Err(Error::Desync(_))));
+    }
+
+    #[test]
+    fn test_relay() {
+        let relay = Relay::bind("127.0.0.1:0", 2).unwrap();
+        let address = relay.local_addr().unwrap();
+        let server = thread::spawn(move || relay.run());
+        let x = TcpTransport::connect(address).unwrap();
+        let y = TcpTransport::connect(address).unwrap();
+        let mut a = Session::join(x, 3).unwrap();
+        let mut b = Session::join(y, 3).unwrap();
+        assert_eq!((a.player(), b.player()), (0, 1));
+        run(&mut a, &mut b, 120);
+        drop((a, b));
+        server.join().unwrap().unwrap();
+    }
+}
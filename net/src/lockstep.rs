@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::message::Message;
+
+/// Copies of the simulation disagreed on their state after `tick`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Desync {
+    pub tick: u32,
+}
+
+/// Bookkeeping for a lockstep simulation, where every peer steps the same
+/// tick with the same inputs. Local input is scheduled `delay` ticks ahead,
+/// giving it time to reach remote peers before it is needed.
+pub struct Lockstep {
+    player: usize,
+    players: usize,
+    delay: u32,
+    tick: u32,      // next tick to simulate
+    scheduled: u32, // next tick to schedule local input for
+    inputs: HashMap<u32, Vec<Option<u32>>>,
+    checksums: HashMap<u32, Vec<Option<u64>>>,
+}
+
+impl Lockstep {
+    pub fn new(player: usize, players: usize, delay: u32) -> Self {
+        Lockstep {
+            player,
+            players,
+            delay,
+            tick: 0,
+            scheduled: delay,
+            // no one has input for the ticks within the initial delay
+            inputs: (0..delay)
+                .map(|tick| (tick, vec![Some(0); players]))
+                .collect(),
+            checksums: HashMap::new(),
+        }
+    }
+
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Schedule local input for the next available tick, returning the
+    /// message to send to peers, or `None` if input is already scheduled as
+    /// far ahead as the delay allows.
+    pub fn schedule(&mut self, input: u32) -> Option<Message> {
+        if self.tick + self.delay < self.scheduled {
+            return None;
+        }
+        let tick = self.scheduled;
+        self.scheduled += 1;
+        self.set_input(self.player, tick, input);
+        Some(Message::Input {
+            player: self.player as u8,
+            tick,
+            input,
+        })
+    }
+
+    pub fn receive(&mut self, message: &Message) -> Result<(), Desync> {
+        match *message {
+            Message::Input {
+                player,
+                tick,
+                input,
+            } => {
+                self.set_input(player as usize, tick, input);
+                Ok(())
+            }
+            Message::Checksum { player, tick, hash } => {
+                self.set_checksum(player as usize, tick, hash)
+            }
+            Message::Welcome { .. } => Ok(()),
+        }
+    }
+
+    /// Inputs of every player for the current tick, if they have all arrived.
+    pub fn inputs(&self) -> Option<Vec<u32>> {
+        (self.inputs.get(&self.tick)?.iter()).cloned().collect()
+    }
+
+    /// Move to the next tick, after the current one was simulated resulting in
+    /// state with `checksum`. Returns the message to send to peers.
+    pub fn advance(&mut self, checksum: u64) -> Result<Message, Desync> {
+        let tick = self.tick;
+        self.inputs.remove(&tick);
+        self.tick += 1;
+        self.set_checksum(self.player, tick, checksum)?;
+        Ok(Message::Checksum {
+            player: self.player as u8,
+            tick,
+            hash: checksum,
+        })
+    }
+
+    fn set_input(&mut self, player: usize, tick: u32, input: u32) {
+        if player < self.players && self.tick <= tick {
+            let players = self.players;
+            let inputs = (self.inputs.entry(tick)).or_insert_with(|| vec![None; players]);
+            inputs[player] = Some(input);
+        }
+    }
+
+    fn set_checksum(&mut self, player: usize, tick: u32, hash: u64) -> Result<(), Desync> {
+        if self.players <= player {
+            return Ok(());
+        }
+        let players = self.players;
+        let hashes = (self.checksums.entry(tick)).or_insert_with(|| vec![None; players]);
+        hashes[player] = Some(hash);
+        if hashes.iter().all(Option::is_some) {
+            let agree = hashes.iter().all(|other| *other == hashes[0]);
+            self.checksums.remove(&tick);
+            if !agree {
+                return Err(Desync { tick });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_lockstep {
+    use super::*;
+
+    #[test]
+    fn test_initial_delay() {
+        let mut lockstep = Lockstep::new(0, 2, 3);
+        for _ in 0..3 {
+            assert_eq!(lockstep.inputs(), Some(vec![0, 0]));
+            lockstep.advance(0).unwrap();
+        }
+        assert_eq!(lockstep.inputs(), None);
+    }
+
+    #[test]
+    fn test_schedule_limit() {
+        let mut lockstep = Lockstep::new(0, 1, 2);
+        assert!(lockstep.schedule(1).is_some());
+        assert!(lockstep.schedule(2).is_none());
+        lockstep.advance(0).unwrap();
+        assert!(lockstep.schedule(2).is_some());
+    }
+
+    #[test]
+    fn test_exchange() {
+        let mut a = Lockstep::new(0, 2, 1);
+        let mut b = Lockstep::new(1, 2, 1);
+        a.advance(7).unwrap();
+        b.advance(7).unwrap();
+        let to_b = a.schedule(5).unwrap();
+        assert_eq!(a.inputs(), None);
+        let to_a = b.schedule(6).unwrap();
+        a.receive(&to_a).unwrap();
+        b.receive(&to_b).unwrap();
+        assert_eq!(a.inputs(), Some(vec![5, 6]));
+        assert_eq!(b.inputs(), Some(vec![5, 6]));
+    }
+
+    #[test]
+    fn test_desync() {
+        let mut a = Lockstep::new(0, 2, 1);
+        let mut b = Lockstep::new(1, 2, 1);
+        let to_b = a.advance(1).unwrap();
+        let to_a = b.advance(2).unwrap();
+        assert_eq!(a.receive(&to_a), Err(Desync { tick: 0 }));
+        assert_eq!(b.receive(&to_b), Err(Desync { tick: 0 }));
+    }
+}
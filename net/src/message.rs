@@ -0,0 +1,143 @@
+use std::io;
+
+const WELCOME: u8 = 0;
+const INPUT: u8 = 1;
+const CHECKSUM: u8 = 2;
+
+/// Messages exchanged between peers, or between the relay and a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Welcome { player: u8, players: u8 },
+    Input { player: u8, tick: u32, input: u32 },
+    Checksum { player: u8, tick: u32, hash: u64 },
+}
+
+impl Message {
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Message::Welcome { player, players } => {
+                bytes.extend_from_slice(&[WELCOME, *player, *players]);
+            }
+            Message::Input {
+                player,
+                tick,
+                input,
+            } => {
+                bytes.extend_from_slice(&[INPUT, *player]);
+                bytes.extend_from_slice(&tick.to_le_bytes());
+                bytes.extend_from_slice(&input.to_le_bytes());
+            }
+            Message::Checksum { player, tick, hash } => {
+                bytes.extend_from_slice(&[CHECKSUM, *player]);
+                bytes.extend_from_slice(&tick.to_le_bytes());
+                bytes.extend_from_slice(&hash.to_le_bytes());
+            }
+        }
+    }
+
+    /// Decode a message from the front of `bytes`, returning it along with the
+    /// number of bytes it occupied, or `None` if `bytes` is incomplete.
+    pub fn decode(bytes: &[u8]) -> io::Result<Option<(Message, usize)>> {
+        let length = match bytes.first() {
+            None => return Ok(None),
+            Some(&WELCOME) => 3,
+            Some(&INPUT) => 10,
+            Some(&CHECKSUM) => 14,
+            Some(tag) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown message tag {}", tag),
+                ))
+            }
+        };
+        if bytes.len() < length {
+            return Ok(None);
+        }
+        let player = bytes[1];
+        let message = match bytes[0] {
+            WELCOME => Message::Welcome {
+                player,
+                players: bytes[2],
+            },
+            INPUT => Message::Input {
+                player,
+                tick: read_u32(&bytes[2..6]),
+                input: read_u32(&bytes[6..10]),
+            },
+            _ => Message::Checksum {
+                player,
+                tick: read_u32(&bytes[2..6]),
+                hash: read_u64(&bytes[6..14]),
+            },
+        };
+        Ok(Some((message, length)))
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut array = [0; 4];
+    array.copy_from_slice(bytes);
+    u32::from_le_bytes(array)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut array = [0; 8];
+    array.copy_from_slice(bytes);
+    u64::from_le_bytes(array)
+}
+
+#[cfg(test)]
+mod test_message {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let messages = vec![
+            Message::Welcome {
+                player: 1,
+                players: 2,
+            },
+            Message::Input {
+                player: 0,
+                tick: 70000,
+                input: 4 | 8,
+            },
+            Message::Checksum {
+                player: 1,
+                tick: 3,
+                hash: u64::MAX - 1,
+            },
+        ];
+        let mut bytes = Vec::new();
+        for message in messages.iter() {
+            message.encode(&mut bytes);
+        }
+        let mut decoded = Vec::new();
+        let mut offset = 0;
+        while let Some((message, length)) = Message::decode(&bytes[offset..]).unwrap() {
+            decoded.push(message);
+            offset += length;
+        }
+        assert_eq!(decoded, messages);
+        assert_eq!(offset, bytes.len());
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let mut bytes = Vec::new();
+        Message::Checksum {
+            player: 0,
+            tick: 1,
+            hash: 2,
+        }
+        .encode(&mut bytes);
+        assert!(Message::decode(&bytes[..bytes.len() - 1])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_unknown_tag() {
+        assert!(Message::decode(&[9, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+use crate::message::Message;
+use crate::transport::{TcpTransport, Transport};
+
+/// A server that introduces peers and forwards each message it receives from
+/// one peer to all others.
+pub struct Relay {
+    listener: TcpListener,
+    players: usize,
+}
+
+impl Relay {
+    pub fn bind<A: ToSocketAddrs>(address: A, players: usize) -> io::Result<Self> {
+        Ok(Relay {
+            listener: TcpListener::bind(address)?,
+            players: players.clamp(1, u8::MAX as usize),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Wait for all players to connect, then relay messages until one of them
+    /// disconnects.
+    pub fn run(self) -> io::Result<()> {
+        match self.serve() {
+            Err(error) if is_disconnect(&error) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn serve(self) -> io::Result<()> {
+        let mut peers = Vec::with_capacity(self.players);
+        for _ in 0..self.players {
+            let (stream, _) = self.listener.accept()?;
+            peers.push(TcpTransport::from_stream(stream)?);
+        }
+        for (i, peer) in peers.iter_mut().enumerate() {
+            peer.send(&Message::Welcome {
+                player: i as u8,
+                players: self.players as u8,
+            })?;
+        }
+        loop {
+            let mut idle = true;
+            for i in 0..peers.len() {
+                let message = match peers[i].receive()? {
+                    Some(message) => message,
+                    None => continue,
+                };
+                idle = false;
+                for (j, peer) in peers.iter_mut().enumerate() {
+                    if i != j {
+                        peer.send(&message)?;
+                    }
+                }
+            }
+            if idle {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+fn is_disconnect(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
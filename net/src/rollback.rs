@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use asteroids::geometry::Size;
+use asteroids::history::History;
+use asteroids::{Controls, Level};
+
+use crate::message::Message;
+use crate::TICK;
+
+struct Frame {
+    level: Level, // state before the tick was simulated
+    inputs: Vec<u32>,
+}
+
+/// A rollback simulation of a `Level`: ticks are simulated as soon as local
+/// input is known, predicting that each remote player repeats their most
+/// recent input. When a remote input arrives that differs from its
+/// prediction, the level is restored to the tick of that input and simulated
+/// forward again.
+///
+/// Simulation stalls rather than running more than `window` ticks ahead of
+/// the latest tick for which all inputs have arrived.
+pub struct Rollback {
+    player: usize,
+    players: usize,
+    window: u32,
+    bounds: Size,
+    level: Level,
+    tick: u32, // next tick to simulate
+    inputs: HashMap<u32, Vec<Option<u32>>>,
+    received: Vec<u32>, // next tick expected from each player
+    latest: Vec<u32>,   // latest input received from each player
+    history: History<Frame>,
+    rewind: Option<u32>,
+    rollbacks: u32,
+}
+
+impl Rollback {
+    pub fn new(level: Level, bounds: Size, player: usize, players: usize, window: u32) -> Self {
+        Rollback {
+            player,
+            players,
+            window: window.max(1),
+            bounds,
+            level,
+            tick: 0,
+            inputs: HashMap::new(),
+            received: vec![0; players],
+            latest: vec![0; players],
+            history: History::new(window.max(1) as usize + 1),
+            rewind: None,
+            rollbacks: 0,
+        }
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// The earliest tick for which some input has not yet arrived.
+    pub fn confirmed(&self) -> u32 {
+        self.received.iter().cloned().min().unwrap_or(self.tick)
+    }
+
+    /// The level state before `tick` was simulated, if it is still recorded.
+    pub fn snapshot(&self, tick: u32) -> Option<&Level> {
+        if tick == self.tick {
+            Some(&self.level)
+        } else {
+            self.history.get(tick).map(|frame| &frame.level)
+        }
+    }
+
+    /// The number of times the simulation has been rolled back.
+    pub fn rollbacks(&self) -> u32 {
+        self.rollbacks
+    }
+
+    pub fn receive(&mut self, message: &Message) {
+        if let Message::Input {
+            player,
+            tick,
+            input,
+        } = *message
+        {
+            let player = player as usize;
+            if player < self.players && player != self.player {
+                self.set_input(player, tick, input);
+                let mispredicted =
+                    (self.history.get(tick)).is_some_and(|frame| frame.inputs[player] != input);
+                if tick < self.tick && mispredicted {
+                    self.rewind = Some(self.rewind.map_or(tick, |rewind| rewind.min(tick)));
+                }
+            }
+        }
+    }
+
+    /// Simulate the next tick with local `input`, returning the message to
+    /// send to peers, or `None` if the simulation is stalled waiting for
+    /// remote input.
+    pub fn advance(&mut self, input: u32) -> Option<Message> {
+        self.settle();
+        if self.confirmed() + self.window <= self.tick {
+            return None;
+        }
+        let tick = self.tick;
+        self.set_input(self.player, tick, input);
+        self.simulate();
+        Some(Message::Input {
+            player: self.player as u8,
+            tick,
+            input,
+        })
+    }
+
+    /// Apply any pending rollback, re-simulating up to the current tick.
+    pub fn settle(&mut self) {
+        if let Some(rewind) = self.rewind.take() {
+            let end = self.tick;
+            let frame = self.history.get(rewind).expect("rollback within window");
+            self.level = frame.level.clone();
+            self.tick = rewind;
+            while self.tick < end {
+                self.simulate();
+            }
+            self.rollbacks += 1;
+        }
+    }
+
+    fn simulate(&mut self) {
+        let inputs = match self.inputs.get(&self.tick) {
+            Some(inputs) => (inputs.iter().zip(self.latest.iter()))
+                .map(|(input, latest)| input.unwrap_or(*latest))
+                .collect(),
+            None => self.latest.clone(),
+        };
+        let controls = inputs.iter().map(|&input| Controls::new(input));
+        self.history.push(
+            self.tick,
+            Frame {
+                level: self.level.clone(),
+                inputs: inputs.clone(),
+            },
+        );
+        (self.level).step(TICK, &self.bounds, &controls.collect::<Vec<_>>());
+        self.level.drain_events();
+        self.tick += 1;
+
+        // inputs before the confirmed tick will not be needed again
+        let confirmed = self.confirmed();
+        self.inputs.retain(|tick, _| confirmed <= *tick + 1);
+    }
+
+    fn set_input(&mut self, player: usize, tick: u32, input: u32) {
+        let players = self.players;
+        let inputs = (self.inputs.entry(tick)).or_insert_with(|| vec![None; players]);
+        inputs[player] = Some(input);
+        if self.received[player] <= tick {
+            self.received[player] = tick + 1;
+            self.latest[player] = input;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_rollback {
+    use super::*;
+    use asteroids::Rules;
+    use std::collections::VecDeque;
+
+    const PLAYERS: usize = 2;
+    const TICKS: u32 = 480;
+
+    fn bounds() -> Size {
+        Size {
+            width: 1200.0,
+            height: 900.0,
+        }
+    }
+
+    fn level() -> Level {
+        Level::new(3, &bounds(), &Rules::coop(PLAYERS, true))
+    }
+
+    /// Inputs that change every so often, so predictions are often wrong.
+    fn input(player: usize, tick: u32) -> u32 {
+        let phase = (tick / (7 + 5 * player as u32)) % 4;
+        [4 | 8, 1 | 8, 2 | 4, 16][phase as usize]
+    }
+
+    /// Step a level with every input known up front.
+    fn reference(ticks: u32) -> Level {
+        let mut level = level();
+        for tick in 0..ticks {
+            let controls = (0..PLAYERS)
+                .map(|player| Controls::new(input(player, tick)))
+                .collect::<Vec<_>>();
+            level.step(TICK, &bounds(), &controls);
+        }
+        level
+    }
+
+    /// Run peers that deliver messages to each other after `latency` frames.
+    fn run(latency: u32, window: u32) -> Vec<Rollback> {
+        let mut peers = (0..PLAYERS)
+            .map(|player| Rollback::new(level(), bounds(), player, PLAYERS, window))
+            .collect::<Vec<_>>();
+        let mut wire: VecDeque<(u32, usize, Message)> = VecDeque::new();
+        let mut frame = 0;
+        while peers.iter().any(|peer| peer.confirmed() < TICKS) {
+            while wire
+                .front()
+                .is_some_and(|(arrival, _, _)| *arrival <= frame)
+            {
+                let (_, from, message) = wire.pop_front().unwrap();
+                for (i, peer) in peers.iter_mut().enumerate() {
+                    if i != from {
+                        peer.receive(&message);
+                    }
+                }
+            }
+            for (i, peer) in peers.iter_mut().enumerate() {
+                if peer.tick() < TICKS {
+                    let tick = peer.tick();
+                    if let Some(message) = peer.advance(input(i, tick)) {
+                        wire.push_back((frame + latency, i, message));
+                    }
+                } else {
+                    peer.settle();
+                }
+            }
+            frame += 1;
+        }
+        peers
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        let expected = reference(TICKS).checksum();
+        for peer in run(4, 8).iter() {
+            assert_eq!(peer.tick(), TICKS);
+            assert_eq!(peer.level().checksum(), expected);
+            assert!(0 < peer.rollbacks());
+        }
+    }
+
+    #[test]
+    fn test_no_latency() {
+        let expected = reference(TICKS).checksum();
+        for peer in run(0, 8).iter() {
+            assert_eq!(peer.level().checksum(), expected);
+        }
+    }
+
+    #[test]
+    fn test_stall() {
+        let mut peer = Rollback::new(level(), bounds(), 0, PLAYERS, 3);
+        assert!(peer.advance(0).is_some());
+        assert!(peer.advance(0).is_some());
+        assert!(peer.advance(0).is_some());
+        assert!(peer.advance(0).is_none());
+        peer.receive(&Message::Input {
+            player: 1,
+            tick: 0,
+            input: 0,
+        });
+        assert!(peer.advance(0).is_some());
+        assert_eq!(peer.tick(), 4);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut a = level();
+        let snapshot = a.clone();
+        let controls = [Controls::new(4 | 8), Controls::new(1 | 8)];
+        a.step(TICK, &bounds(), &controls);
+        let mut b = snapshot.clone();
+        b.step(TICK, &bounds(), &controls);
+        assert_eq!(a.checksum(), b.checksum());
+        assert_ne!(a.checksum(), snapshot.checksum());
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::thread;
+
+use crate::message::Message;
+
+/// A connection for sending and polling messages, without blocking on reads.
+pub trait Transport {
+    fn send(&mut self, message: &Message) -> io::Result<()>;
+    /// Take the next message, if one has arrived.
+    fn receive(&mut self) -> io::Result<Option<Message>>;
+}
+
+pub struct TcpTransport {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+}
+
+impl TcpTransport {
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        TcpTransport::from_stream(TcpStream::connect(address)?)
+    }
+
+    pub fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(TcpTransport {
+            stream,
+            buffer: Vec::new(),
+        })
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        message.encode(&mut bytes);
+        let mut written = 0;
+        while written < bytes.len() {
+            match self.stream.write(&bytes[written..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => written += n,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => thread::yield_now(),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Option<Message>> {
+        let mut chunk = [0; 256];
+        loop {
+            if let Some((message, length)) = Message::decode(&self.buffer)? {
+                self.buffer.drain(..length);
+                return Ok(Some(message));
+            }
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// An in-process transport, where each message sent is held back for a
+/// number of polls, to simulate latency deterministically.
+pub struct MemoryTransport {
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    latency: u32, // polls
+    queue: VecDeque<(u32, Message)>,
+}
+
+impl MemoryTransport {
+    /// Create two connected ends.
+    pub fn pair(latency: u32) -> (MemoryTransport, MemoryTransport) {
+        let (a_sender, b_receiver) = mpsc::channel();
+        let (b_sender, a_receiver) = mpsc::channel();
+        (
+            MemoryTransport::new(a_sender, a_receiver, latency),
+            MemoryTransport::new(b_sender, b_receiver, latency),
+        )
+    }
+
+    fn new(sender: Sender<Message>, receiver: Receiver<Message>, latency: u32) -> Self {
+        MemoryTransport {
+            sender,
+            receiver,
+            latency,
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        (self.sender.send(message.clone())).map_err(|_| io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn receive(&mut self) -> io::Result<Option<Message>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(message) => self.queue.push_back((self.latency, message)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        match self.queue.front_mut() {
+            Some((0, _)) => Ok(self.queue.pop_front().map(|(_, message)| message)),
+            Some(_) => {
+                for (wait, _) in self.queue.iter_mut() {
+                    *wait = wait.saturating_sub(1);
+                }
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+}